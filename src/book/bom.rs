@@ -0,0 +1,351 @@
+use crate::{craft::{add, Consumable}, logic::{KeyValue, LogicVisit}, recipe::GenericRecipe};
+
+use super::RecipeBook;
+
+/// Recipe able to produce an item, offered to a [`BomChooser`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate<K> {
+    pub recipe: u32,
+    /// Units of the item one craft yields.
+    pub yields: u32,
+    /// Ingredient alternatives, see [`Consumable::alternatives`].
+    pub alternatives: Vec<Vec<KeyValue<K>>>,
+}
+
+/// Picked candidate and alternative, by position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Choice {
+    pub candidate: usize,
+    pub alternative: usize,
+}
+
+/// Decides how to make an item; `None` keeps it as a raw resource instead of expanding it.
+pub trait BomChooser<K> {
+    fn choose(&mut self, item: &K, candidates: &[Candidate<K>]) -> Option<Choice>;
+    /// Position of the tool alternative to obtain for `recipe`, which makes `item`. The first
+    /// one by default.
+    fn choose_tools(&mut self, _item: &K, _recipe: u32, _alternatives: &[Vec<KeyValue<K>>]) -> usize {
+        0
+    }
+}
+
+impl<K, F: FnMut(&K, &[Candidate<K>]) -> Option<Choice>> BomChooser<K> for F {
+    fn choose(&mut self, item: &K, candidates: &[Candidate<K>]) -> Option<Choice> {
+        self(item, candidates)
+    }
+}
+
+/// Lowest MSG index and its first alternative.
+pub struct FirstRecipe;
+
+impl<K> BomChooser<K> for FirstRecipe {
+    fn choose(&mut self, _item: &K, _candidates: &[Candidate<K>]) -> Option<Choice> {
+        Some(Choice { candidate: 0, alternative: 0 })
+    }
+}
+
+/// Cheapest alternative per unit produced, by a caller-provided per-item cost of one step's
+/// ingredients. Ties go to the lower MSG index. Tools are picked by the same cost.
+pub struct CheapestBy<F>(pub F);
+
+impl<F> CheapestBy<F> {
+    fn cost<K>(&self, alternative: &[KeyValue<K>]) -> u64 where F: Fn(&K) -> u64 {
+        alternative.iter().fold(0u64, |total, kv| total.saturating_add(u64::from(kv.value).saturating_mul((self.0)(&kv.key))))
+    }
+}
+
+impl<K, F: Fn(&K) -> u64> BomChooser<K> for CheapestBy<F> {
+    fn choose_tools(&mut self, _item: &K, _recipe: u32, alternatives: &[Vec<KeyValue<K>>]) -> usize {
+        (0..alternatives.len()).min_by_key(|&position| self.cost(&alternatives[position])).unwrap_or(0)
+    }
+    fn choose(&mut self, _item: &K, candidates: &[Candidate<K>]) -> Option<Choice> {
+        let mut best: Option<(u64, u32, Choice)> = None;
+        for (candidate_pos, candidate) in candidates.iter().enumerate() {
+            for (alternative_pos, alternative) in candidate.alternatives.iter().enumerate() {
+                let cost = self.cost(alternative);
+                // cost / yields < best_cost / best_yields, without division
+                let cheaper = best.map_or(true, |(best_cost, best_yields, _)| {
+                    u128::from(cost) * u128::from(best_yields) < u128::from(best_cost) * u128::from(candidate.yields)
+                });
+                if cheaper {
+                    best = Some((cost, candidate.yields, Choice { candidate: candidate_pos, alternative: alternative_pos }));
+                }
+            }
+        }
+        best.map(|(_, _, choice)| choice)
+    }
+}
+
+/// One recipe of a bill of materials, crafted `crafts` times.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BomStep<'a, K, L> {
+    pub recipe: u32,
+    pub crafts: u32,
+    /// Ingredients of the chosen alternative for all crafts.
+    pub consumed: Vec<KeyValue<K>>,
+    /// Whole output for all crafts, surplus included.
+    pub produced: Vec<KeyValue<K>>,
+    /// Needed during the step, never consumed. Made once per bill, see [`BillOfMaterials::tools`].
+    pub tools: Option<&'a L>,
+    /// Skills required at this step.
+    pub params_to_craft: Option<&'a L>,
+    pub params_to_see: Option<&'a L>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillOfMaterials<'a, K, L> {
+    /// Base resources to gather, summed per key.
+    pub raw: Vec<KeyValue<K>>,
+    /// Steps in crafting order, each after the steps making its ingredients and tools.
+    pub steps: Vec<BomStep<'a, K, L>>,
+    /// Tools of the steps, the alternative [`BomChooser::choose_tools`] picks, obtained once for
+    /// the whole bill and expanded like ingredients. Raw ones are in `raw` as well.
+    pub tools: Vec<KeyValue<K>>,
+    /// Surplus from outputs larger than needed and byproducts.
+    pub leftovers: Vec<KeyValue<K>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BomError {
+    /// Expanding an item led back to itself through these recipes, outermost first.
+    Cycle(Vec<u32>),
+    /// Chooser returned a position out of range. For tools `candidate` is 0.
+    InvalidChoice(Choice),
+}
+
+impl std::fmt::Display for BomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BomError::Cycle(recipes) => {
+                write!(f, "recipes form a cycle:")?;
+                for recipe in recipes {
+                    write!(f, " #{recipe}")?;
+                }
+                Ok(())
+            }
+            BomError::InvalidChoice(choice) => write!(f, "chooser picked candidate {} alternative {}, which don't exist", choice.candidate, choice.alternative),
+        }
+    }
+}
+
+impl std::error::Error for BomError {}
+
+/// Choices dropped for leading into a cycle, per [`RecipeBook::bill_of_materials`] call. Each
+/// retry expands a subtree again, so without a bound nested ORs over a cycle are exponential.
+const MAX_RETRIES: u32 = 1_000;
+
+impl<K: Clone + PartialEq, S, L: LogicVisit<Key = K> + Consumable<Key = K>> RecipeBook<GenericRecipe<S, L>> {
+    /// Expands `quantity` of `item` down to raw resources, crafting intermediates through the
+    /// recipes `chooser` picks. Surplus of earlier steps is used before crafting more. A choice
+    /// leading back to an item being expanded is dropped and `chooser` asked again among the
+    /// rest, up to a fixed number of retries per bill; [`BomError::Cycle`] when no choice is
+    /// left or the retries run out.
+    pub fn bill_of_materials(&self, item: &K, quantity: u32, chooser: &mut impl BomChooser<K>) -> Result<BillOfMaterials<'_, K, L>, BomError> {
+        let mut bom = BillOfMaterials { raw: Vec::new(), steps: Vec::new(), tools: Vec::new(), leftovers: Vec::new() };
+        let mut retries = MAX_RETRIES;
+        self.expand(item, quantity, chooser, &mut Vec::new(), &mut retries, &mut bom)?;
+        bom.leftovers.retain(|kv| kv.value > 0);
+        Ok(bom)
+    }
+
+    fn expand<'a>(
+        &'a self,
+        item: &K,
+        quantity: u32,
+        chooser: &mut impl BomChooser<K>,
+        stack: &mut Vec<(K, u32)>,
+        retries: &mut u32,
+        bom: &mut BillOfMaterials<'a, K, L>,
+    ) -> Result<(), BomError> {
+        let mut needed = quantity;
+        if let Some(leftover) = bom.leftovers.iter_mut().find(|kv| kv.key == *item) {
+            let used = leftover.value.min(needed);
+            leftover.value -= used;
+            needed -= used;
+        }
+        if needed == 0 {
+            return Ok(());
+        }
+        if let Some(position) = stack.iter().position(|(key, _)| key == item) {
+            return Err(BomError::Cycle(stack[position..].iter().map(|(_, recipe)| *recipe).collect()));
+        }
+        let mut candidates: Vec<Candidate<K>> = self.crafts().filter_map(|(&index, recipe)| {
+            let yields: u32 = recipe.output.key_values().filter(|kv| kv.key == *item).map(|kv| kv.value).sum();
+            (yields > 0).then(|| Candidate { recipe: index, yields, alternatives: recipe.ingredients.alternatives() })
+        }).collect();
+        candidates.retain(|candidate| !candidate.alternatives.is_empty());
+        let mut cycle = None;
+        loop {
+            let choice = if candidates.is_empty() { None } else { chooser.choose(item, &candidates) };
+            let Some(choice) = choice else {
+                if let Some(cycle) = cycle {
+                    return Err(cycle);
+                }
+                add(&mut bom.raw, item, needed);
+                return Ok(());
+            };
+            let Some((candidate, alternative)) = candidates.get(choice.candidate)
+                .and_then(|candidate| Some((candidate, candidate.alternatives.get(choice.alternative)?)))
+            else {
+                return Err(BomError::InvalidChoice(choice));
+            };
+            let before = (bom.raw.clone(), bom.leftovers.clone(), bom.tools.clone(), bom.steps.len());
+            match self.step(item, needed, candidate, alternative, chooser, stack, retries, bom) {
+                Err(error @ BomError::Cycle(_)) if *retries > 0 => {
+                    *retries -= 1;
+                    (bom.raw, bom.leftovers, bom.tools) = (before.0, before.1, before.2);
+                    bom.steps.truncate(before.3);
+                    cycle = Some(error);
+                    let candidate = &mut candidates[choice.candidate];
+                    candidate.alternatives.remove(choice.alternative);
+                    if candidate.alternatives.is_empty() {
+                        candidates.remove(choice.candidate);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn step<'a>(
+        &'a self,
+        item: &K,
+        needed: u32,
+        candidate: &Candidate<K>,
+        alternative: &[KeyValue<K>],
+        chooser: &mut impl BomChooser<K>,
+        stack: &mut Vec<(K, u32)>,
+        retries: &mut u32,
+        bom: &mut BillOfMaterials<'a, K, L>,
+    ) -> Result<(), BomError> {
+        let recipe = &self.recipes[&candidate.recipe];
+        let crafts = needed.div_ceil(candidate.yields);
+        let consumed: Vec<KeyValue<K>> = alternative.iter().map(|kv| KeyValue::new(kv.key.clone(), kv.value.saturating_mul(crafts))).collect();
+
+        let tools = match &recipe.tools {
+            Some(tools) => {
+                let mut alternatives = tools.alternatives();
+                let position = chooser.choose_tools(item, candidate.recipe, &alternatives);
+                if position >= alternatives.len() {
+                    return Err(BomError::InvalidChoice(Choice { candidate: 0, alternative: position }));
+                }
+                alternatives.swap_remove(position)
+            }
+            None => Vec::new(),
+        };
+
+        stack.push((item.clone(), candidate.recipe));
+        let expanded = tools.iter().try_for_each(|kv| {
+            let owned = bom.tools.iter().find(|tool| tool.key == kv.key).map_or(0, |tool| tool.value);
+            if owned >= kv.value {
+                return Ok(());
+            }
+            add(&mut bom.tools, &kv.key, kv.value - owned);
+            self.expand(&kv.key, kv.value - owned, chooser, stack, retries, bom)
+        }).and_then(|()| consumed.iter().try_for_each(|kv| self.expand(&kv.key, kv.value, chooser, stack, retries, bom)));
+        stack.pop();
+        expanded?;
+
+        let produced: Vec<KeyValue<K>> = recipe.output.key_values().map(|kv| KeyValue::new(kv.key.clone(), kv.value.saturating_mul(crafts))).collect();
+        for kv in &produced {
+            let surplus = if kv.key == *item { kv.value.saturating_sub(needed) } else { kv.value };
+            if surplus > 0 {
+                add(&mut bom.leftovers, &kv.key, surplus);
+            }
+        }
+        bom.steps.push(BomStep {
+            recipe: candidate.recipe,
+            crafts,
+            consumed,
+            produced,
+            tools: recipe.tools.as_ref(),
+            params_to_craft: recipe.params_to_craft.as_ref(),
+            params_to_see: recipe.params_to_see.as_ref(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::{node_book, pairs};
+
+    #[test]
+    fn expands_to_raw() {
+        let book = node_book(&[
+            (1, "PID_EMPTY_JET@@@SK_SCIENCE 50@PID_METAL 2@PID_TOOLS 1@PID_EMPTY_JET 5@exp 10"),
+            (2, "PID_METAL@@@@PID_ORE 3|PID_SCRAP 1@PID_FORGE 1@PID_METAL 1&PID_SLAG 1@exp 10"),
+        ]);
+        let bom = book.bill_of_materials(&"PID_EMPTY_JET".to_owned(), 7, &mut FirstRecipe).unwrap();
+        assert_eq!(vec![("PID_TOOLS", 1), ("PID_FORGE", 1), ("PID_ORE", 12)], pairs(&bom.raw));
+        assert_eq!(vec![("PID_TOOLS", 1), ("PID_FORGE", 1)], pairs(&bom.tools));
+        assert_eq!(vec![(2, 4), (1, 2)], bom.steps.iter().map(|step| (step.recipe, step.crafts)).collect::<Vec<_>>());
+        assert_eq!(vec![("PID_SLAG", 4), ("PID_EMPTY_JET", 3)], pairs(&bom.leftovers));
+        assert!(bom.steps[1].params_to_craft.is_some() && bom.steps[0].tools.is_some());
+
+        let cheap = book.bill_of_materials(&"PID_EMPTY_JET".to_owned(), 5, &mut CheapestBy(|key: &String| if key == "PID_SCRAP" { 2 } else { 1 })).unwrap();
+        assert_eq!(vec![("PID_TOOLS", 1), ("PID_FORGE", 1), ("PID_SCRAP", 2)], pairs(&cheap.raw));
+
+        let keep_metal = |item: &String, _: &[Candidate<String>]| (item != "PID_METAL").then_some(Choice { candidate: 0, alternative: 0 });
+        let bom = book.bill_of_materials(&"PID_EMPTY_JET".to_owned(), 5, &mut { keep_metal }).unwrap();
+        assert_eq!(vec![("PID_TOOLS", 1), ("PID_METAL", 2)], pairs(&bom.raw));
+    }
+
+    #[test]
+    fn chosen_tools() {
+        let book = node_book(&[
+            (1, "PID_LOG@@@@PID_TREE 1@PID_KNIFE 1|PID_AXE 1@PID_LOG 1@exp 10"),
+            (2, "PID_AXE@@@@PID_ORE 2@@PID_AXE 1@exp 10"),
+        ]);
+        let bom = book.bill_of_materials(&"PID_LOG".to_owned(), 1, &mut FirstRecipe).unwrap();
+        assert_eq!(vec![("PID_KNIFE", 1), ("PID_TREE", 1)], pairs(&bom.raw));
+        let cheap = book.bill_of_materials(&"PID_LOG".to_owned(), 1, &mut CheapestBy(|key: &String| if key == "PID_KNIFE" { 50 } else { 1 })).unwrap();
+        assert_eq!(vec![("PID_AXE", 1)], pairs(&cheap.tools));
+        assert_eq!(vec![("PID_ORE", 2), ("PID_TREE", 1)], pairs(&cheap.raw));
+    }
+
+    #[test]
+    fn tools_once() {
+        let book = node_book(&[
+            (1, "PID_SPEAR@@@@PID_STICK 1&PID_BLADE 1@PID_KNIFE 1@PID_SPEAR 1@exp 10"),
+            (2, "PID_BLADE@@@@PID_METAL 2@PID_KNIFE 1@PID_BLADE 1@exp 10"),
+            (3, "PID_KNIFE@@@@PID_METAL 1&PID_STICK 1@@PID_KNIFE 1@exp 10"),
+        ]);
+        let bom = book.bill_of_materials(&"PID_SPEAR".to_owned(), 2, &mut FirstRecipe).unwrap();
+        assert_eq!(vec![3, 2, 1], bom.steps.iter().map(|step| step.recipe).collect::<Vec<_>>());
+        assert_eq!(vec![("PID_METAL", 5), ("PID_STICK", 3)], pairs(&bom.raw));
+        assert_eq!(vec![("PID_KNIFE", 1)], pairs(&bom.tools));
+    }
+
+    #[test]
+    fn cycles() {
+        let book = node_book(&[
+            (1, "PID_A@@@@PID_B 1@@PID_A 1@exp 10"),
+            (2, "PID_B@@@@PID_A 1@@PID_B 1@exp 10"),
+        ]);
+        assert_eq!(Err(BomError::Cycle(vec![1, 2])), book.bill_of_materials(&"PID_A".to_owned(), 1, &mut FirstRecipe));
+
+        let book = node_book(&[
+            (1, "PID_A@@@@PID_B 1@@PID_A 1@exp 10"),
+            (2, "PID_B@@@@PID_A 1|PID_ORE 2@@PID_B 1@exp 10"),
+            (3, "PID_A@@@@PID_ORE 5@@PID_A 1@exp 10"),
+        ]);
+        let bom = book.bill_of_materials(&"PID_A".to_owned(), 1, &mut FirstRecipe).unwrap();
+        assert_eq!(vec![("PID_ORE", 2)], pairs(&bom.raw));
+        assert_eq!(vec![2, 1], bom.steps.iter().map(|step| step.recipe).collect::<Vec<_>>());
+        let bom = book.bill_of_materials(&"PID_B".to_owned(), 1, &mut FirstRecipe).unwrap();
+        assert_eq!(vec![("PID_ORE", 5)], pairs(&bom.raw));
+    }
+
+    #[test]
+    fn bounded_retries() {
+        let lines: Vec<_> = (0..30).flat_map(|n| ["I", "J"].map(|item| {
+            let ingredients = if n == 29 { "PID_I0 1".to_owned() } else { format!("PID_I{} 1|PID_J{} 1", n + 1, n + 1) };
+            (n * 2 + u32::from(item == "J"), format!("PID_{item}{n}@@@@{ingredients}@@PID_{item}{n} 1@exp 1"))
+        })).collect();
+        let book = node_book(&lines.iter().map(|(index, line)| (*index, line.as_str())).collect::<Vec<_>>());
+        assert!(matches!(book.bill_of_materials(&"PID_I0".to_owned(), 1, &mut FirstRecipe), Err(BomError::Cycle(_))));
+    }
+}
@@ -0,0 +1,315 @@
+use std::fmt::{self, Display};
+
+use crate::{craft::Semantic, logic::{KeyValue, LogicVisit}, recipe::{GenericRecipe, RecipeField, SideEffect}};
+
+use super::RecipeBook;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DiffOptions {
+    /// Pair recipes by internal name first, so a recipe moved to another index is reported as
+    /// renumbered instead of removed and added. Leftovers are still paired by index.
+    pub match_by_name: bool,
+}
+
+/// Differences between two books, see [`RecipeBook::diff`]. `Display` renders a changelog.
+#[derive(Debug)]
+pub struct BookDiff<'a, S, L: LogicVisit> {
+    pub added: Vec<(u32, &'a GenericRecipe<S, L>)>,
+    pub removed: Vec<(u32, &'a GenericRecipe<S, L>)>,
+    pub renumbered: Vec<Renumbered<'a, S>>,
+    pub changed: Vec<RecipeChange<'a, S, L::Key>>,
+}
+
+impl<S, L: LogicVisit> BookDiff<'_, S, L> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renumbered.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Renumbered<'a, S> {
+    pub name: &'a S,
+    pub old_index: u32,
+    pub new_index: u32,
+}
+
+/// Paired recipes with at least one changed field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeChange<'a, S, K> {
+    pub old_index: u32,
+    pub new_index: u32,
+    /// Name in the new book.
+    pub name: &'a S,
+    pub fields: Vec<FieldChange<'a, S, K>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldChange<'a, S, K> {
+    Name { old: &'a S, new: &'a S },
+    Description { old: Option<&'a S>, new: Option<&'a S> },
+    Requirement {
+        field: RecipeField,
+        keys: Vec<KeyChange<'a, K>>,
+        /// Operators changed in a way thresholds alone don't explain. Reordering alone isn't a change.
+        restructured: bool,
+    },
+    SideEffect { old: &'a SideEffect<S>, new: &'a SideEffect<S> },
+}
+
+impl<S, K> FieldChange<'_, S, K> {
+    pub fn field(&self) -> RecipeField {
+        match self {
+            FieldChange::Name { .. } => RecipeField::Name,
+            FieldChange::Description { .. } => RecipeField::Description,
+            FieldChange::Requirement { field, .. } => *field,
+            FieldChange::SideEffect { .. } => RecipeField::SideEffect,
+        }
+    }
+}
+
+/// Key-values are paired by key, repeated keys in order of appearance.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyChange<'a, K> {
+    Added(&'a KeyValue<K>),
+    Removed(&'a KeyValue<K>),
+    Threshold { key: &'a K, old: u32, new: u32 },
+}
+
+impl<K: Clone + PartialEq, S: PartialEq, L: LogicVisit<Key = K> + Semantic<Key = K> + Clone> RecipeBook<GenericRecipe<S, L>> {
+    /// Changes from `self` to `new`. Recipes are paired by MSG index, or by name first with
+    /// [`DiffOptions::match_by_name`].
+    pub fn diff<'a>(&'a self, new: &'a Self, options: DiffOptions) -> BookDiff<'a, S, L> {
+        let mut old_left: Vec<u32> = self.recipes.keys().copied().collect();
+        let mut new_left: Vec<u32> = new.recipes.keys().copied().collect();
+        let mut pairs = Vec::new();
+        let mut take_pairs = |matches: &dyn Fn(u32, u32) -> bool, old_left: &mut Vec<u32>, new_left: &mut Vec<u32>| {
+            new_left.retain(|&new_index| {
+                match old_left.iter().position(|&old_index| matches(old_index, new_index)) {
+                    Some(position) => {
+                        pairs.push((old_left.remove(position), new_index));
+                        false
+                    }
+                    None => true,
+                }
+            });
+        };
+        if options.match_by_name {
+            take_pairs(&|old_index, new_index| old_index == new_index && self[&old_index].name == new[&new_index].name, &mut old_left, &mut new_left);
+            take_pairs(&|old_index, new_index| self[&old_index].name == new[&new_index].name, &mut old_left, &mut new_left);
+        }
+        take_pairs(&|old_index, new_index| old_index == new_index, &mut old_left, &mut new_left);
+        pairs.sort_by_key(|&(_, new_index)| new_index);
+
+        let mut renumbered = Vec::new();
+        let mut changed = Vec::new();
+        for (old_index, new_index) in pairs {
+            let (old_recipe, new_recipe) = (&self[&old_index], &new[&new_index]);
+            if old_index != new_index {
+                renumbered.push(Renumbered { name: &new_recipe.name, old_index, new_index });
+            }
+            let fields = recipe_changes(old_recipe, new_recipe);
+            if !fields.is_empty() {
+                changed.push(RecipeChange { old_index, new_index, name: &new_recipe.name, fields });
+            }
+        }
+        BookDiff {
+            added: new_left.into_iter().map(|index| (index, &new[&index])).collect(),
+            removed: old_left.into_iter().map(|index| (index, &self[&index])).collect(),
+            renumbered,
+            changed,
+        }
+    }
+}
+
+fn recipe_changes<'a, K, S, L>(old: &'a GenericRecipe<S, L>, new: &'a GenericRecipe<S, L>) -> Vec<FieldChange<'a, S, K>>
+where
+    K: Clone + PartialEq,
+    S: PartialEq,
+    L: LogicVisit<Key = K> + Semantic<Key = K> + Clone,
+{
+    let mut fields = Vec::new();
+    if old.name != new.name {
+        fields.push(FieldChange::Name { old: &old.name, new: &new.name });
+    }
+    if old.description != new.description {
+        fields.push(FieldChange::Description { old: old.description.as_ref(), new: new.description.as_ref() });
+    }
+    let blocks = [
+        (RecipeField::ParamsToSee, old.params_to_see.as_ref(), new.params_to_see.as_ref()),
+        (RecipeField::ParamsToCraft, old.params_to_craft.as_ref(), new.params_to_craft.as_ref()),
+        (RecipeField::Ingredients, Some(&old.ingredients), Some(&new.ingredients)),
+        (RecipeField::Tools, old.tools.as_ref(), new.tools.as_ref()),
+        (RecipeField::Output, Some(&old.output), Some(&new.output)),
+    ];
+    for (field, old, new) in blocks {
+        if let Some(change) = block_change(field, old, new) {
+            fields.push(change);
+        }
+    }
+    if old.side_effect != new.side_effect {
+        fields.push(FieldChange::SideEffect { old: &old.side_effect, new: &new.side_effect });
+    }
+    fields
+}
+
+fn block_change<'a, K, S, L>(field: RecipeField, old: Option<&'a L>, new: Option<&'a L>) -> Option<FieldChange<'a, S, K>>
+where
+    K: Clone + PartialEq,
+    L: LogicVisit<Key = K> + Semantic<Key = K> + Clone,
+{
+    let old_kvs: Vec<_> = old.into_iter().flat_map(LogicVisit::key_values).collect();
+    let mut new_kvs: Vec<Option<&KeyValue<K>>> = new.into_iter().flat_map(LogicVisit::key_values).map(Some).collect();
+    let mut keys = Vec::new();
+    for old_kv in old_kvs {
+        let paired = new_kvs.iter_mut().find(|new_kv| new_kv.is_some_and(|new_kv| new_kv.key == old_kv.key));
+        match paired.and_then(Option::take) {
+            Some(new_kv) if new_kv.value != old_kv.value => {
+                keys.push(KeyChange::Threshold { key: &new_kv.key, old: old_kv.value, new: new_kv.value })
+            }
+            Some(_) => {}
+            None => keys.push(KeyChange::Removed(old_kv)),
+        }
+    }
+    keys.extend(new_kvs.into_iter().flatten().map(KeyChange::Added));
+
+    // same keys with every threshold at 1 compare the operators only
+    let restructured = match (old, new) {
+        (Some(old), Some(new)) if keys.iter().all(|change| matches!(change, KeyChange::Threshold { .. })) => {
+            let unit = |logic: &L| logic.with_values(|_, _| Ok::<_, ()>(1)).ok();
+            match (unit(old), unit(new)) {
+                (Some(old), Some(new)) => !old.equivalent(&new),
+                _ => false,
+            }
+        }
+        _ => false,
+    };
+    (!keys.is_empty() || restructured).then_some(FieldChange::Requirement { field, keys, restructured })
+}
+
+impl<S: Display, L: LogicVisit> Display for BookDiff<'_, S, L>
+where
+    L::Key: Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No changes.");
+        }
+        if !self.added.is_empty() {
+            writeln!(f, "Added:")?;
+            for (index, recipe) in &self.added {
+                writeln!(f, "- #{index} {}", recipe.name)?;
+            }
+        }
+        if !self.removed.is_empty() {
+            writeln!(f, "Removed:")?;
+            for (index, recipe) in &self.removed {
+                writeln!(f, "- #{index} {}", recipe.name)?;
+            }
+        }
+        if !self.renumbered.is_empty() {
+            writeln!(f, "Renumbered:")?;
+            for Renumbered { name, old_index, new_index } in &self.renumbered {
+                writeln!(f, "- {name}: #{old_index} -> #{new_index}")?;
+            }
+        }
+        if !self.changed.is_empty() {
+            writeln!(f, "Changed:")?;
+            for change in &self.changed {
+                writeln!(f, "- #{} {}", change.new_index, change.name)?;
+                for field in &change.fields {
+                    write!(f, "  - {}: ", field.field())?;
+                    write_field_change(f, field)?;
+                    writeln!(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_field_change<S: Display, K: Display>(f: &mut fmt::Formatter<'_>, change: &FieldChange<S, K>) -> fmt::Result {
+    match change {
+        FieldChange::Name { old, new } => write!(f, "{old} -> {new}"),
+        FieldChange::Description { .. } => write!(f, "changed"),
+        FieldChange::Requirement { keys, restructured, .. } => {
+            for (i, key) in keys.iter().enumerate() {
+                if i != 0 {
+                    write!(f, ", ")?;
+                }
+                match key {
+                    KeyChange::Added(kv) => write!(f, "added {} {}", kv.key, kv.value)?,
+                    KeyChange::Removed(kv) => write!(f, "removed {} {}", kv.key, kv.value)?,
+                    KeyChange::Threshold { key, old, new } => write!(f, "{key} {old} -> {new}")?,
+                }
+            }
+            if *restructured {
+                write!(f, "{}conditions restructured", if keys.is_empty() { "" } else { ", " })?;
+            }
+            Ok(())
+        }
+        FieldChange::SideEffect { old, new } => {
+            write_side_effect(f, old)?;
+            write!(f, " -> ")?;
+            write_side_effect(f, new)
+        }
+    }
+}
+
+fn write_side_effect<S: Display>(f: &mut fmt::Formatter<'_>, side_effect: &SideEffect<S>) -> fmt::Result {
+    match side_effect {
+        SideEffect::Script { module, function } => write!(f, "script {module}@{function}"),
+        SideEffect::Experience(exp) => write!(f, "exp {exp}"),
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    #[test]
+    fn diff_by_index() {
+        let old = node_book(&[
+            (1, "PID_A@@@@PID_X 2|PID_Y 1@@PID_A 1@exp 10"),
+            (2, "PID_B@@@@PID_X 1@@PID_B 1@exp 10"),
+        ]);
+        let new = node_book(&[
+            (1, "PID_A@@@@PID_Y 1|PID_X 3&PID_Z 1@@PID_A 1@exp 20"),
+            (3, "PID_B@@@@PID_X 1@@PID_B 1@exp 10"),
+        ]);
+        let diff = old.diff(&new, DiffOptions::default());
+        assert_eq!(vec![3], diff.added.iter().map(|(index, _)| *index).collect::<Vec<_>>());
+        assert_eq!(vec![2], diff.removed.iter().map(|(index, _)| *index).collect::<Vec<_>>());
+        assert!(diff.renumbered.is_empty());
+        let z = KeyValue::new("PID_Z".to_owned(), 1);
+        assert_eq!(&FieldChange::Requirement {
+            field: RecipeField::Ingredients,
+            keys: vec![KeyChange::Threshold { key: &"PID_X".to_owned(), old: 2, new: 3 }, KeyChange::Added(&z)],
+            restructured: false,
+        }, &diff.changed[0].fields[0]);
+        assert_eq!(concat!(
+            "Added:\n- #3 PID_B\n",
+            "Removed:\n- #2 PID_B\n",
+            "Changed:\n- #1 PID_A\n",
+            "  - ingredients: PID_X 2 -> 3, added PID_Z 1\n",
+            "  - side_effect: exp 10 -> exp 20\n",
+        ), diff.to_string());
+    }
+
+    #[test]
+    fn diff_by_name() {
+        let old = node_book(&[
+            (1, "PID_A@@@@PID_X 1|PID_Y 1@@PID_A 1@exp 10"),
+            (2, "PID_B@@@@PID_X 1@@PID_B 1@exp 10"),
+        ]);
+        let new = node_book(&[
+            (1, "PID_A@@@@PID_X 1&PID_Y 1@@PID_A 1@exp 10"),
+            (5, "PID_B@@@@PID_X 1@@PID_B 1@exp 10"),
+        ]);
+        let diff = old.diff(&new, DiffOptions { match_by_name: true });
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(vec![Renumbered { name: &"PID_B".to_owned(), old_index: 2, new_index: 5 }], diff.renumbered);
+        assert_eq!("Renumbered:\n- PID_B: #2 -> #5\nChanged:\n- #1 PID_A\n  - ingredients: conditions restructured\n", diff.to_string());
+        assert!(old.diff(&old, DiffOptions::default()).is_empty());
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::BTreeMap;
+
+use crate::{craft::Requirement, logic::LogicVisit, recipe::GenericRecipe};
+
+use super::RecipeBook;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphNode<'a, K> {
+    Item(&'a K),
+    Recipe(u32),
+}
+
+/// Recipes feeding each other's inputs in a loop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle<'a, K> {
+    pub recipes: Vec<u32>,
+    pub items: Vec<&'a K>,
+}
+
+struct RecipeNode<'a, L> {
+    index: u32,
+    ingredients: &'a L,
+    tools: Option<&'a L>,
+}
+
+/// Production graph with items and recipes as nodes: ingredients and tools point to the recipe,
+/// the recipe points to its output. Params aren't part of it. Every alternative of an OR counts
+/// as an edge.
+pub struct CraftGraph<'a, K, L> {
+    items: BTreeMap<&'a K, usize>,
+    recipes: Vec<RecipeNode<'a, L>>,
+    /// Edges by node id, items first, then recipes in book order.
+    edges: Vec<Vec<usize>>,
+    produced: Vec<bool>,
+}
+
+impl<K: Ord, S, L: LogicVisit<Key = K> + Requirement<Key = K>> RecipeBook<GenericRecipe<S, L>> {
+    pub fn craft_graph(&self) -> CraftGraph<'_, K, L> {
+        let mut items = BTreeMap::new();
+        for (_, recipe) in self.crafts() {
+            let blocks = [Some(&recipe.ingredients), recipe.tools.as_ref(), Some(&recipe.output)];
+            for kv in blocks.into_iter().flatten().flat_map(LogicVisit::key_values) {
+                items.entry(&kv.key).or_insert(0);
+            }
+        }
+        for (id, item) in items.values_mut().enumerate() {
+            *item = id;
+        }
+        let mut recipes = Vec::new();
+        let mut produced = vec![false; items.len()];
+        let mut edges = vec![Vec::new(); items.len()];
+        for (&index, recipe) in self.crafts() {
+            let id = edges.len();
+            edges.push(Vec::new());
+            for kv in [Some(&recipe.ingredients), recipe.tools.as_ref()].into_iter().flatten().flat_map(LogicVisit::key_values) {
+                edges[items[&kv.key]].push(id);
+            }
+            for kv in recipe.output.key_values() {
+                edges[id].push(items[&kv.key]);
+                produced[items[&kv.key]] = true;
+            }
+            recipes.push(RecipeNode { index, ingredients: &recipe.ingredients, tools: recipe.tools.as_ref() });
+        }
+        CraftGraph { items, recipes, edges, produced }
+    }
+}
+
+impl<'a, K: Ord, L: Requirement<Key = K>> CraftGraph<'a, K, L> {
+    pub fn items(&self) -> impl Iterator<Item = &'a K> + '_ {
+        self.items.keys().copied()
+    }
+    /// Items no recipe produces.
+    pub fn base_resources(&self) -> Vec<&'a K> {
+        self.items.iter().filter(|(_, &id)| !self.produced[id]).map(|(item, _)| *item).collect()
+    }
+    /// Items which can't be obtained starting from base resources, because every recipe
+    /// producing them needs something unobtainable, e.g. only a cycle leads to them.
+    pub fn unreachable_items(&self) -> Vec<&'a K> {
+        let mut reachable: Vec<bool> = self.produced.iter().map(|produced| !produced).collect();
+        let mut crafted = vec![false; self.recipes.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, recipe) in self.recipes.iter().enumerate() {
+                let obtainable = |logic: &L| logic.evaluate_with(&mut |kv| reachable[self.items[&kv.key]]);
+                if crafted[id] || !obtainable(recipe.ingredients) || !recipe.tools.map_or(true, obtainable) {
+                    continue;
+                }
+                crafted[id] = true;
+                changed = true;
+                for &item in &self.edges[self.items.len() + id] {
+                    reachable[item] = true;
+                }
+            }
+        }
+        self.items.iter().filter(|(_, &id)| !reachable[id]).map(|(item, _)| *item).collect()
+    }
+    /// Strongly connected groups of recipes, including a recipe consuming its own output.
+    pub fn cycles(&self) -> Vec<Cycle<'a, K>> {
+        let mut cycles: Vec<Cycle<'a, K>> = self.components()
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|component| {
+                let mut cycle = Cycle { recipes: Vec::new(), items: Vec::new() };
+                let items: Vec<&'a K> = self.items.keys().copied().collect();
+                for id in component {
+                    match id.checked_sub(self.items.len()) {
+                        Some(recipe) => cycle.recipes.push(self.recipes[recipe].index),
+                        None => cycle.items.push(items[id]),
+                    }
+                }
+                cycle.recipes.sort_unstable();
+                cycle.items.sort_unstable();
+                cycle
+            })
+            .collect();
+        cycles.sort_by_key(|cycle| cycle.recipes.clone());
+        cycles
+    }
+    /// Every node after all nodes pointing to it, or the cycles preventing such an order.
+    /// Ties go to items before recipes, then to key and MSG index order.
+    pub fn topological_order(&self) -> Result<Vec<GraphNode<'a, K>>, Vec<Cycle<'a, K>>> {
+        let mut incoming = vec![0usize; self.edges.len()];
+        for &to in self.edges.iter().flatten() {
+            incoming[to] += 1;
+        }
+        let items: Vec<&'a K> = self.items.keys().copied().collect();
+        let mut ready: std::collections::BTreeSet<usize> = (0..self.edges.len()).filter(|&id| incoming[id] == 0).collect();
+        let mut order = Vec::with_capacity(self.edges.len());
+        while let Some(id) = ready.pop_first() {
+            order.push(match id.checked_sub(self.items.len()) {
+                Some(recipe) => GraphNode::Recipe(self.recipes[recipe].index),
+                None => GraphNode::Item(items[id]),
+            });
+            for &to in &self.edges[id] {
+                incoming[to] -= 1;
+                if incoming[to] == 0 {
+                    ready.insert(to);
+                }
+            }
+        }
+        if order.len() == self.edges.len() {
+            Ok(order)
+        } else {
+            Err(self.cycles())
+        }
+    }
+
+    /// Tarjan's strongly connected components.
+    fn components(&self) -> Vec<Vec<usize>> {
+        struct Tarjan<'e> {
+            edges: &'e [Vec<usize>],
+            next: usize,
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            stack: Vec<usize>,
+            on_stack: Vec<bool>,
+            components: Vec<Vec<usize>>,
+        }
+        impl Tarjan<'_> {
+            fn visit(&mut self, id: usize) {
+                self.index[id] = Some(self.next);
+                self.low[id] = self.next;
+                self.next += 1;
+                self.stack.push(id);
+                self.on_stack[id] = true;
+                for &to in &self.edges[id] {
+                    match self.index[to] {
+                        None => {
+                            self.visit(to);
+                            self.low[id] = self.low[id].min(self.low[to]);
+                        }
+                        Some(index) if self.on_stack[to] => self.low[id] = self.low[id].min(index),
+                        Some(_) => {}
+                    }
+                }
+                if Some(self.low[id]) == self.index[id] {
+                    let mut component = Vec::new();
+                    while let Some(top) = self.stack.pop() {
+                        self.on_stack[top] = false;
+                        component.push(top);
+                        if top == id {
+                            break;
+                        }
+                    }
+                    self.components.push(component);
+                }
+            }
+        }
+        let len = self.edges.len();
+        let mut tarjan = Tarjan {
+            edges: &self.edges,
+            next: 0,
+            index: vec![None; len],
+            low: vec![0; len],
+            stack: Vec::new(),
+            on_stack: vec![false; len],
+            components: Vec::new(),
+        };
+        for id in 0..len {
+            if tarjan.index[id].is_none() {
+                tarjan.visit(id);
+            }
+        }
+        tarjan.components
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    #[test]
+    fn acyclic() {
+        let book = node_book(&[
+            (1, "PID_JERKY@@@@PID_MEAT 4|PID_RAD_MEAT 4@PID_FIRE 1@PID_JERKY 3@exp 10"),
+            (2, "PID_MEAT@@@@PID_BRAHMIN 1@PID_KNIFE 1@PID_MEAT 4@exp 10"),
+        ]);
+        let graph = book.craft_graph();
+        let key = |key: &str| key.to_owned();
+        let (brahmin, fire, knife, rad_meat, meat, jerky) = (key("PID_BRAHMIN"), key("PID_FIRE"), key("PID_KNIFE"), key("PID_RAD_MEAT"), key("PID_MEAT"), key("PID_JERKY"));
+        assert_eq!(vec![&brahmin, &fire, &knife, &rad_meat], graph.base_resources());
+        assert!(graph.unreachable_items().is_empty());
+        assert!(graph.cycles().is_empty());
+        assert_eq!(Ok(vec![
+            GraphNode::Item(&brahmin),
+            GraphNode::Item(&fire),
+            GraphNode::Item(&knife),
+            GraphNode::Item(&rad_meat),
+            GraphNode::Recipe(2),
+            GraphNode::Item(&meat),
+            GraphNode::Recipe(1),
+            GraphNode::Item(&jerky),
+        ]), graph.topological_order());
+    }
+
+    #[test]
+    fn cyclic() {
+        let book = node_book(&[
+            (1, "PID_A@@@@PID_B 1@@PID_A 1@exp 10"),
+            (2, "PID_B@@@@PID_A 1@@PID_B 1@exp 10"),
+            (3, "PID_C@@@@PID_A 1|PID_ORE 1@@PID_C 1@exp 10"),
+            (4, "PID_D@@@@PID_D 1&PID_ORE 1@@PID_D 2@exp 10"),
+        ]);
+        let graph = book.craft_graph();
+        let items = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        let unreachable = items(&["PID_A", "PID_B", "PID_D"]);
+        assert_eq!(unreachable.iter().collect::<Vec<_>>(), graph.unreachable_items());
+        let cycles = graph.topological_order().unwrap_err();
+        assert_eq!(vec![vec![1, 2], vec![4]], cycles.iter().map(|cycle| cycle.recipes.clone()).collect::<Vec<_>>());
+        assert_eq!(unreachable[..2].iter().collect::<Vec<_>>(), cycles[0].items);
+    }
+}
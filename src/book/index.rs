@@ -0,0 +1,96 @@
+use std::collections::BTreeMap;
+
+use crate::{logic::LogicVisit, recipe::{GenericRecipe, RecipeField}};
+
+use super::RecipeBook;
+
+/// One appearance of a key in a recipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub recipe: u32,
+    /// One of the requirement blocks or `Output`.
+    pub field: RecipeField,
+    pub quantity: u32,
+}
+
+/// Reverse index from key to every recipe mentioning it, see [`RecipeBook::key_index`].
+#[derive(Debug, Clone)]
+pub struct KeyIndex<'a, K> {
+    usages: BTreeMap<&'a K, Vec<Usage>>,
+}
+
+impl<'a, K: Ord> KeyIndex<'a, K> {
+    /// Usages ordered by recipe index, then by field.
+    pub fn usages(&self, key: &K) -> &[Usage] {
+        self.usages.get(key).map_or(&[], Vec::as_slice)
+    }
+    pub fn in_field<'s>(&'s self, key: &K, field: RecipeField) -> impl Iterator<Item = &'s Usage> + 's {
+        self.usages(key).iter().filter(move |usage| usage.field == field)
+    }
+    /// Recipes with `key` in their output.
+    pub fn produced_by<'s>(&'s self, key: &K) -> impl Iterator<Item = &'s Usage> + 's {
+        self.in_field(key, RecipeField::Output)
+    }
+    /// Recipes consuming `key` as an ingredient or requiring it as a tool.
+    pub fn used_by<'s>(&'s self, key: &K) -> impl Iterator<Item = &'s Usage> + 's {
+        self.usages(key).iter().filter(|usage| matches!(usage.field, RecipeField::Ingredients | RecipeField::Tools))
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &'a K> + '_ {
+        self.usages.keys().copied()
+    }
+}
+
+impl<K: Ord, S, L: LogicVisit<Key = K>> RecipeBook<GenericRecipe<S, L>> {
+    pub fn key_index(&self) -> KeyIndex<'_, K> {
+        let mut usages: BTreeMap<&K, Vec<Usage>> = BTreeMap::new();
+        for (&index, recipe) in self.crafts() {
+            let blocks = [
+                (RecipeField::ParamsToSee, recipe.params_to_see.as_ref()),
+                (RecipeField::ParamsToCraft, recipe.params_to_craft.as_ref()),
+                (RecipeField::Ingredients, Some(&recipe.ingredients)),
+                (RecipeField::Tools, recipe.tools.as_ref()),
+                (RecipeField::Output, Some(&recipe.output)),
+            ];
+            for (field, logic) in blocks {
+                for kv in logic.into_iter().flat_map(LogicVisit::key_values) {
+                    usages.entry(&kv.key).or_default().push(Usage { recipe: index, field, quantity: kv.value });
+                }
+            }
+        }
+        KeyIndex { usages }
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::{book::RecipeBook, Recipe, tests::node_book};
+
+    #[test]
+    fn string_keys() {
+        let book = node_book(&[
+            (1, "PID_KNIFE@@@PE 2@PID_IRON 2@PID_HAMMER 1@PID_KNIFE 1@exp 10"),
+            (2, "PID_MEAT@@@@PID_BRAHMIN 1@PID_KNIFE 1@PID_MEAT 4|PID_HIDE 1@exp 10"),
+            (3, "PID_STEW@@@@PID_MEAT 2&PID_MEAT 1@@PID_STEW 1@exp 10"),
+        ]);
+        let index = book.key_index();
+        let knife = "PID_KNIFE".to_owned();
+        assert_eq!(vec![1], index.produced_by(&knife).map(|usage| usage.recipe).collect::<Vec<_>>());
+        assert_eq!(vec![Usage { recipe: 2, field: RecipeField::Tools, quantity: 1 }], index.used_by(&knife).copied().collect::<Vec<_>>());
+        assert_eq!(vec![4, 2, 1], index.usages(&"PID_MEAT".to_owned()).iter().map(|usage| usage.quantity).collect::<Vec<_>>());
+        assert_eq!(1, index.in_field(&"PE".to_owned(), RecipeField::ParamsToCraft).count());
+        assert!(index.usages(&"PID_NOTHING".to_owned()).is_empty());
+    }
+
+    #[test]
+    fn numeric_keys() {
+        let book = RecipeBook::<Recipe<&str, u32>>::try_from_iter(std::iter::once(
+            (1, "!PID_A@@1 217 1 50 1 0 2 217 218 2 100 100 2 1 0 3 1 2 3 3 4 5 6 3 0 1 0 0 0 1 0 1 9 1 1 exp"),
+        )).unwrap();
+        let index = book.key_index();
+        let fields: Vec<_> = index.usages(&217).iter().map(|usage| (usage.field, usage.quantity)).collect();
+        assert_eq!(vec![(RecipeField::ParamsToSee, 50), (RecipeField::ParamsToCraft, 100)], fields);
+        assert_eq!(1, index.produced_by(&9).count());
+        assert_eq!(vec![1, 2, 3, 9, 217, 218], index.keys().copied().collect::<Vec<_>>());
+    }
+}
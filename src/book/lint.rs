@@ -0,0 +1,292 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{craft::Semantic, logic::LogicVisit, recipe::{GenericRecipe, RecipeField}};
+
+use super::{pattern::NamePatterns, RecipeBook};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintRule {
+    /// Internal name used by an earlier recipe.
+    DuplicateName,
+    /// Key-value with quantity or threshold 0.
+    ZeroQuantity,
+    /// Same key more than once in one block.
+    RepeatedKey,
+    /// Item both consumed and required as a tool.
+    IngredientIsTool,
+    /// Item crafted out of itself.
+    OutputIsIngredient,
+    /// `params_to_craft` adds nothing, everyone seeing the recipe satisfies it.
+    WeakCraftParams,
+    EmptyDescription,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 7] = [
+        LintRule::DuplicateName,
+        LintRule::ZeroQuantity,
+        LintRule::RepeatedKey,
+        LintRule::IngredientIsTool,
+        LintRule::OutputIsIngredient,
+        LintRule::WeakCraftParams,
+        LintRule::EmptyDescription,
+    ];
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            LintRule::DuplicateName => "duplicate_name",
+            LintRule::ZeroQuantity => "zero_quantity",
+            LintRule::RepeatedKey => "repeated_key",
+            LintRule::IngredientIsTool => "ingredient_is_tool",
+            LintRule::OutputIsIngredient => "output_is_ingredient",
+            LintRule::WeakCraftParams => "weak_craft_params",
+            LintRule::EmptyDescription => "empty_description",
+        }
+    }
+    pub fn default_severity(self) -> Severity {
+        match self {
+            LintRule::DuplicateName | LintRule::ZeroQuantity | LintRule::OutputIsIngredient => Severity::Error,
+            LintRule::RepeatedKey | LintRule::IngredientIsTool | LintRule::WeakCraftParams | LintRule::EmptyDescription => Severity::Warning,
+        }
+    }
+}
+
+impl Display for LintRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// Enabled rules with their severities, and recipes exempt from every rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintConfig {
+    rules: BTreeMap<LintRule, Option<Severity>>,
+    exempt: NamePatterns,
+}
+
+impl Default for LintConfig {
+    /// Every rule at its default severity, `PID_ZAPLATKA_CRAFT_*` section separators exempt.
+    fn default() -> Self {
+        LintConfig {
+            rules: LintRule::ALL.into_iter().map(|rule| (rule, Some(rule.default_severity()))).collect(),
+            exempt: NamePatterns::separators(),
+        }
+    }
+}
+
+impl LintConfig {
+    /// `None` when disabled.
+    pub fn severity(&self, rule: LintRule) -> Option<Severity> {
+        self.rules.get(&rule).copied().flatten()
+    }
+    pub fn set_severity(&mut self, rule: LintRule, severity: Severity) -> &mut Self {
+        self.rules.insert(rule, Some(severity));
+        self
+    }
+    pub fn enable(&mut self, rule: LintRule) -> &mut Self {
+        self.set_severity(rule, rule.default_severity())
+    }
+    pub fn disable(&mut self, rule: LintRule) -> &mut Self {
+        self.rules.insert(rule, None);
+        self
+    }
+    /// Exempts recipes by internal name, a trailing `*` matches any suffix.
+    pub fn exempt(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.exempt.push(pattern.into());
+        self
+    }
+    pub fn clear_exemptions(&mut self) -> &mut Self {
+        self.exempt.clear();
+        self
+    }
+    pub fn is_exempt(&self, name: &str) -> bool {
+        self.exempt.matches(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub recipe: u32,
+    pub field: Option<RecipeField>,
+    pub message: String,
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}[{}] #{}", self.severity, self.rule, self.recipe)?;
+        if let Some(field) = self.field {
+            write!(f, " {field}")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Findings in MSG order. `Display` writes one per line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LintReport {
+    pub lints: Vec<Lint>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.errors().next().is_some()
+    }
+    pub fn errors(&self) -> impl Iterator<Item = &Lint> {
+        self.lints.iter().filter(|lint| lint.severity == Severity::Error)
+    }
+    pub fn warnings(&self) -> impl Iterator<Item = &Lint> {
+        self.lints.iter().filter(|lint| lint.severity == Severity::Warning)
+    }
+}
+
+impl Display for LintReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for lint in &self.lints {
+            writeln!(f, "{lint}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<K, S, L> RecipeBook<GenericRecipe<S, L>>
+where
+    K: Clone + PartialEq + Display,
+    S: AsRef<str>,
+    L: LogicVisit<Key = K> + Semantic<Key = K>,
+{
+    /// Separators marked by [`RecipeBook::detect_separators`] are skipped like exempt names.
+    pub fn lint(&self, config: &LintConfig) -> LintReport {
+        let mut lints = Vec::new();
+        let mut names: BTreeMap<&str, u32> = BTreeMap::new();
+        for (&index, recipe) in self.crafts() {
+            let name = recipe.name.as_ref();
+            if config.is_exempt(name) {
+                continue;
+            }
+            let mut push = |rule: LintRule, field: Option<RecipeField>, message: String| {
+                if let Some(severity) = config.severity(rule) {
+                    lints.push(Lint { rule, severity, recipe: index, field, message });
+                }
+            };
+            match names.get(name) {
+                Some(first) => push(LintRule::DuplicateName, Some(RecipeField::Name), format!("`{name}` is already used by #{first}")),
+                None => {
+                    names.insert(name, index);
+                }
+            }
+            if recipe.description.as_ref().map_or(true, |description| description.as_ref().trim().is_empty()) {
+                push(LintRule::EmptyDescription, Some(RecipeField::Description), "description is empty".to_owned());
+            }
+            let blocks = [
+                (RecipeField::ParamsToSee, recipe.params_to_see.as_ref()),
+                (RecipeField::ParamsToCraft, recipe.params_to_craft.as_ref()),
+                (RecipeField::Ingredients, Some(&recipe.ingredients)),
+                (RecipeField::Tools, recipe.tools.as_ref()),
+                (RecipeField::Output, Some(&recipe.output)),
+            ];
+            for (field, logic) in blocks {
+                let kvs: Vec<_> = logic.into_iter().flat_map(LogicVisit::key_values).collect();
+                for (i, kv) in kvs.iter().enumerate() {
+                    if kv.value == 0 {
+                        push(LintRule::ZeroQuantity, Some(field), format!("`{}` has quantity 0", kv.key));
+                    }
+                    let first = kvs.iter().position(|other| other.key == kv.key) == Some(i);
+                    if first && kvs[i + 1..].iter().any(|other| other.key == kv.key) {
+                        push(LintRule::RepeatedKey, Some(field), format!("`{}` appears more than once", kv.key));
+                    }
+                }
+            }
+            let ingredients: Vec<&K> = recipe.ingredients.keys().collect();
+            let mut reported: Vec<&K> = Vec::new();
+            for key in recipe.tools.iter().flat_map(LogicVisit::keys) {
+                if ingredients.contains(&key) && !reported.contains(&key) {
+                    reported.push(key);
+                    push(LintRule::IngredientIsTool, Some(RecipeField::Tools), format!("`{key}` is both an ingredient and a tool"));
+                }
+            }
+            reported.clear();
+            for key in recipe.output.keys() {
+                if ingredients.contains(&key) && !reported.contains(&key) {
+                    reported.push(key);
+                    push(LintRule::OutputIsIngredient, Some(RecipeField::Output), format!("`{key}` is crafted out of itself"));
+                }
+            }
+            if let (Some(see), Some(craft)) = (&recipe.params_to_see, &recipe.params_to_craft) {
+                if see.implies(craft) {
+                    push(LintRule::WeakCraftParams, Some(RecipeField::ParamsToCraft), "always satisfied once params_to_see is".to_owned());
+                }
+            }
+        }
+        LintReport { lints }
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    fn book() -> crate::UserFriendlyRecipeBook {
+        node_book(&[
+            (1, "PID_ZAPLATKA_CRAFT_FOOD@@@@PID_ZAPLATKA_CRAFT_FOOD 1@@PID_ZAPLATKA_CRAFT_FOOD 1@exp 0"),
+            (2, "PID_JERKY@Dried meat.@SK_OUTDOORSMAN 50@SK_OUTDOORSMAN 30@PID_MEAT 4&PID_MEAT 1|PID_KNIFE 1@PID_KNIFE 1@PID_JERKY 3@exp 10"),
+            (3, "PID_JERKY@@@@PID_JERKY 1&PID_SALT 0@@PID_JERKY 2@exp 10"),
+        ])
+    }
+
+    #[test]
+    fn default_rules() {
+        let report = book().lint(&LintConfig::default());
+        let found: Vec<_> = report.lints.iter().map(|lint| (lint.recipe, lint.rule)).collect();
+        assert_eq!(vec![
+            (2, LintRule::RepeatedKey),
+            (2, LintRule::IngredientIsTool),
+            (2, LintRule::WeakCraftParams),
+            (3, LintRule::DuplicateName),
+            (3, LintRule::EmptyDescription),
+            (3, LintRule::ZeroQuantity),
+            (3, LintRule::OutputIsIngredient),
+        ], found);
+        assert!(report.has_errors());
+        assert_eq!(4, report.warnings().count());
+        assert_eq!("error[duplicate_name] #3 name: `PID_JERKY` is already used by #2", report.lints[3].to_string());
+    }
+
+    #[test]
+    fn configured_rules() {
+        let mut config = LintConfig::default();
+        config
+            .disable(LintRule::DuplicateName)
+            .disable(LintRule::ZeroQuantity)
+            .set_severity(LintRule::OutputIsIngredient, Severity::Warning)
+            .clear_exemptions()
+            .exempt("PID_JERKY");
+        let mut book = book();
+        assert!(book.lint(&config).lints.is_empty());
+        // without detected separators only the exemptions kept #1 out
+        book.detect_separators(crate::book::SeparatorConfig::default().clear_patterns());
+        let report = book.lint(&config);
+        assert!(!report.has_errors());
+        assert_eq!(vec![1, 1], report.lints.iter().map(|lint| lint.recipe).collect::<Vec<_>>());
+        assert_eq!(Some(LintRule::WeakCraftParams), LintRule::from_name("weak_craft_params"));
+    }
+}
@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::recipe::GenericRecipe;
+
+use super::RecipeBook;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Fail on the first recipe a later layer tries to place over an existing one.
+    #[default]
+    Error,
+    LastWins,
+    KeepFirst,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeOptions {
+    pub policy: ConflictPolicy,
+    /// Recipes of later layers replace the earlier recipe with the same internal name, keeping
+    /// its MSG index. Recipes with a new name still conflict over an occupied index.
+    pub match_by_name: bool,
+}
+
+/// Recipe a layer disables, in the layers below it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deletion {
+    Index(u32),
+    Name(String),
+}
+
+/// One MSG file of a stack, e.g. the base book or a mod.
+#[derive(Debug)]
+pub struct Layer<R> {
+    name: String,
+    book: RecipeBook<R>,
+    deletions: Vec<Deletion>,
+}
+
+impl<R> Layer<R> {
+    pub fn new(name: impl Into<String>, book: RecipeBook<R>) -> Self {
+        Layer { name: name.into(), book, deletions: Vec::new() }
+    }
+    /// Deletions apply before the layer's own recipes, so a layer can delete and re-add a
+    /// recipe even under [`ConflictPolicy::KeepFirst`]. Deleting a missing recipe is a no-op.
+    pub fn delete(mut self, deletion: Deletion) -> Self {
+        self.deletions.push(deletion);
+        self
+    }
+}
+
+/// Where a merged recipe came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// Position of the layer in the stack.
+    pub layer: usize,
+    /// MSG index of the recipe within that layer.
+    pub source_index: u32,
+    /// Layers whose recipe at this index was replaced, bottom first.
+    pub overridden: Vec<usize>,
+}
+
+#[derive(Debug)]
+pub struct MergedBook<R> {
+    book: RecipeBook<R>,
+    provenance: BTreeMap<u32, Provenance>,
+    layers: Vec<String>,
+}
+
+impl<R> MergedBook<R> {
+    pub fn book(&self) -> &RecipeBook<R> {
+        &self.book
+    }
+    pub fn into_book(self) -> RecipeBook<R> {
+        self.book
+    }
+    pub fn provenance(&self, index: u32) -> Option<&Provenance> {
+        self.provenance.get(&index)
+    }
+    pub fn layer_name(&self, layer: usize) -> Option<&str> {
+        self.layers.get(layer).map(String::as_str)
+    }
+}
+
+/// Two layers placed a recipe at the same index under [`ConflictPolicy::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict {
+    pub index: u32,
+    pub existing_layer: String,
+    pub layer: String,
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "recipe #{} of layer `{}` conflicts with layer `{}`", self.index, self.layer, self.existing_layer)
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+impl<S: AsRef<str>, L> RecipeBook<GenericRecipe<S, L>> {
+    /// Stacks `layers` bottom to top into one book.
+    pub fn overlay(layers: impl IntoIterator<Item = Layer<GenericRecipe<S, L>>>, options: MergeOptions) -> Result<MergedBook<GenericRecipe<S, L>>, MergeConflict> {
+        // the flag marks section separators of the source layer
+        let mut recipes: BTreeMap<u32, (GenericRecipe<S, L>, Provenance, bool)> = BTreeMap::new();
+        let mut names: Vec<String> = Vec::new();
+        for (layer, Layer { name, book, deletions }) in layers.into_iter().enumerate() {
+            names.push(name);
+            for deletion in deletions {
+                match deletion {
+                    Deletion::Index(index) => {
+                        recipes.remove(&index);
+                    }
+                    Deletion::Name(name) => recipes.retain(|_, (recipe, _, _)| recipe.name.as_ref() != name),
+                }
+            }
+            let RecipeBook { recipes: layer_recipes, separators } = book;
+            for (source_index, recipe) in layer_recipes {
+                // only layers below count, a layer may repeat a name within itself
+                let slot = options.match_by_name
+                    .then(|| {
+                        recipes.iter()
+                            .find(|(_, (known, origin, _))| origin.layer < layer && known.name.as_ref() == recipe.name.as_ref())
+                            .map(|(index, _)| *index)
+                    })
+                    .flatten()
+                    .unwrap_or(source_index);
+                let mut provenance = Provenance { layer, source_index, overridden: Vec::new() };
+                if let Some((_, existing, _)) = recipes.get(&slot) {
+                    match options.policy {
+                        ConflictPolicy::Error => {
+                            return Err(MergeConflict { index: slot, existing_layer: names[existing.layer].clone(), layer: names[layer].clone() });
+                        }
+                        ConflictPolicy::KeepFirst => continue,
+                        ConflictPolicy::LastWins => {
+                            provenance.overridden = existing.overridden.clone();
+                            provenance.overridden.push(existing.layer);
+                        }
+                    }
+                }
+                recipes.insert(slot, (recipe, provenance, separators.contains(&source_index)));
+            }
+        }
+        let mut book = RecipeBook::default();
+        let mut provenance = BTreeMap::new();
+        for (index, (recipe, origin, separator)) in recipes {
+            book.recipes.insert(index, recipe);
+            provenance.insert(index, origin);
+            if separator {
+                book.separators.insert(index);
+            }
+        }
+        Ok(MergedBook { book, provenance, layers: names })
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    fn layers() -> Vec<Layer<crate::ReadableOwnedNodeRecipe>> {
+        vec![
+            Layer::new("base", node_book(&[
+                (1, "PID_A@@@@PID_X 1@@PID_A 1@exp 10"),
+                (2, "PID_B@@@@PID_X 1@@PID_B 1@exp 10"),
+                (3, "PID_C@@@@PID_X 1@@PID_C 1@exp 10"),
+            ])),
+            Layer::new("mod", node_book(&[
+                (7, "PID_A@@@@PID_Y 1@@PID_A 1@exp 10"),
+                (2, "PID_D@@@@PID_X 1@@PID_D 1@exp 10"),
+            ])).delete(Deletion::Name("PID_C".to_owned())),
+        ]
+    }
+
+    fn names(book: &RecipeBook<crate::ReadableOwnedNodeRecipe>) -> Vec<(u32, &str)> {
+        book.iter().map(|(index, recipe)| (*index, recipe.name.as_str())).collect()
+    }
+
+    #[test]
+    fn policies() {
+        let by_index = |policy| RecipeBook::overlay(layers(), MergeOptions { policy, match_by_name: false });
+        assert_eq!(
+            MergeConflict { index: 2, existing_layer: "base".to_owned(), layer: "mod".to_owned() },
+            by_index(ConflictPolicy::Error).unwrap_err(),
+        );
+        let first = by_index(ConflictPolicy::KeepFirst).unwrap();
+        assert_eq!(vec![(1, "PID_A"), (2, "PID_B"), (7, "PID_A")], names(first.book()));
+
+        let last = by_index(ConflictPolicy::LastWins).unwrap();
+        assert_eq!(vec![(1, "PID_A"), (2, "PID_D"), (7, "PID_A")], names(last.book()));
+        assert_eq!(Some(&Provenance { layer: 1, source_index: 2, overridden: vec![0] }), last.provenance(2));
+        assert_eq!(Some("mod"), last.layer_name(1));
+    }
+
+    #[test]
+    fn by_name() {
+        let merged = RecipeBook::overlay(layers(), MergeOptions { policy: ConflictPolicy::LastWins, match_by_name: true }).unwrap();
+        assert_eq!(vec![(1, "PID_A"), (2, "PID_D")], names(merged.book()));
+        assert_eq!(Some(&Provenance { layer: 1, source_index: 7, overridden: vec![0] }), merged.provenance(1));
+    }
+
+    #[test]
+    fn duplicate_name_within_layer() {
+        let layers = vec![
+            Layer::new("base", node_book(&[
+                (1, "PID_A@@@@PID_X 1@@PID_A 1@exp 10"),
+                (4, "PID_A@@@@PID_Y 1@@PID_A 1@exp 10"),
+            ])),
+            Layer::new("mod", node_book(&[(9, "PID_B@@@@PID_X 1@@PID_B 1@exp 10")])),
+        ];
+        let merged = RecipeBook::overlay(layers, MergeOptions { policy: ConflictPolicy::Error, match_by_name: true }).unwrap();
+        assert_eq!(vec![(1, "PID_A"), (4, "PID_A"), (9, "PID_B")], names(merged.book()));
+    }
+
+    #[test]
+    fn keeps_separators() {
+        let base = node_book(&[
+            (1, "PID_ZAPLATKA_CRAFT_TOOLS@@@@PID_ZAPLATKA_CRAFT_TOOLS 1@@PID_ZAPLATKA_CRAFT_TOOLS 1@exp 0"),
+            (2, "PID_A@@@@PID_X 1@@PID_A 1@exp 10"),
+        ]);
+        let layers = vec![Layer::new("base", base), Layer::new("mod", node_book(&[(3, "PID_B@@@@PID_X 1@@PID_B 1@exp 10")]))];
+        let merged = RecipeBook::overlay(layers, MergeOptions::default()).unwrap();
+        assert_eq!(vec![1], merged.book().separators().map(|(index, _)| *index).collect::<Vec<_>>());
+    }
+}
@@ -1,13 +1,36 @@
-use std::{collections::BTreeMap, ops::Deref};
+use std::{collections::{BTreeMap, BTreeSet}, ops::Deref};
+
+mod diff;
+mod merge;
+mod index;
+mod graph;
+mod bom;
+mod lint;
+mod section;
+mod multilingual;
+mod pattern;
+
+pub use self::{
+    diff::{DiffOptions, BookDiff, Renumbered, RecipeChange, FieldChange, KeyChange},
+    merge::{ConflictPolicy, MergeOptions, Deletion, Layer, Provenance, MergedBook, MergeConflict},
+    index::{Usage, KeyIndex},
+    graph::{CraftGraph, GraphNode, Cycle},
+    bom::{Candidate, Choice, BomChooser, FirstRecipe, CheapestBy, BomStep, BillOfMaterials, BomError},
+    lint::{LintRule, Severity, LintConfig, Lint, LintReport},
+    section::{SeparatorConfig, Section},
+    multilingual::{MultilingualBook, LanguageMismatch, MismatchKind},
+};
 
 #[derive(Debug)]
 pub struct RecipeBook<R> {
     pub(crate) recipes: BTreeMap<u32, R>,
+    /// Indices of section separators, see [`RecipeBook::detect_separators`].
+    pub(crate) separators: BTreeSet<u32>,
 }
 
 impl<R> Default for RecipeBook<R> {
     fn default() -> Self {
-        Self { recipes: Default::default() }
+        Self { recipes: Default::default(), separators: Default::default() }
     }
 }
 
@@ -36,7 +59,7 @@ impl<S, K> RecipeBook<S, K> {
 impl<R> RecipeBook<R> {
     pub fn map_recipes<R2, E, F: Fn(R)->Result<R2, E>>(self, map: F) -> Result<RecipeBook<R2>, E> {
         let res: Result<_, E> = self.recipes.into_iter().map(|(num, recipe)| Ok((num, map(recipe)?))).collect();
-        Ok(RecipeBook { recipes: res? })
+        Ok(RecipeBook { recipes: res?, separators: self.separators })
     }
 }
 /*
@@ -0,0 +1,226 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::{craft::Semantic, logic::LogicVisit, recipe::{GenericRecipe, RecipeField}};
+
+use super::{DiffOptions, RecipeBook};
+
+/// Language variants of one book, e.g. `text/engl` and `text/russ`. Requirements, names and
+/// indices come from the primary language, only descriptions are kept per language.
+#[derive(Debug)]
+pub struct MultilingualBook<S, L> {
+    /// Primary language book, descriptions included.
+    book: RecipeBook<GenericRecipe<S, L>>,
+    /// Recipe index in the primary language -> language code -> description, other languages only.
+    descriptions: BTreeMap<u32, BTreeMap<String, S>>,
+    languages: Vec<String>,
+}
+
+/// Recipe of a secondary language which doesn't line up with the primary language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageMismatch {
+    pub language: String,
+    /// Index in the primary language, or in `language` for [`MismatchKind::Extra`].
+    pub index: u32,
+    pub kind: MismatchKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// No recipe at this index or with this name in the language.
+    Missing,
+    /// Recipe exists only in the language.
+    Extra,
+    /// Same recipe under another index.
+    Renumbered { index: u32 },
+    /// Fields other than the description differ, requirements are compared by meaning.
+    Fields(Vec<RecipeField>),
+}
+
+impl Display for LanguageMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}] #{}", self.language, self.index)?;
+        match &self.kind {
+            MismatchKind::Missing => write!(f, " is missing"),
+            MismatchKind::Extra => write!(f, " exists only in this language"),
+            MismatchKind::Renumbered { index } => write!(f, " is at #{index}"),
+            MismatchKind::Fields(fields) => {
+                write!(f, " differs in ")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", field.name())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<S, L> MultilingualBook<S, L> {
+    /// `book` becomes the shared requirement data and keeps the primary descriptions.
+    pub fn new(language: impl Into<String>, book: RecipeBook<GenericRecipe<S, L>>) -> Self {
+        Self { book, descriptions: BTreeMap::new(), languages: vec![language.into()] }
+    }
+    /// Shared requirement data with descriptions of the primary language, so it lints and
+    /// serializes as that language's book.
+    pub fn book(&self) -> &RecipeBook<GenericRecipe<S, L>> {
+        &self.book
+    }
+    pub fn primary_language(&self) -> &str {
+        &self.languages[0]
+    }
+    /// Language codes, primary first.
+    pub fn languages(&self) -> impl Iterator<Item = &str> {
+        self.languages.iter().map(String::as_str)
+    }
+    pub fn description(&self, index: u32, language: &str) -> Option<&S> {
+        if language == self.primary_language() {
+            return self.book.get(&index)?.description.as_ref();
+        }
+        self.descriptions.get(&index)?.get(language)
+    }
+    /// Every translation of the recipe's description, by language code, primary first.
+    pub fn descriptions(&self, index: u32) -> impl Iterator<Item = (&str, &S)> {
+        let primary = self.book.get(&index).and_then(|recipe| recipe.description.as_ref()).map(|description| (self.primary_language(), description));
+        let others = self.descriptions.get(&index).into_iter().flatten().map(|(language, description)| (language.as_str(), description));
+        primary.into_iter().chain(others)
+    }
+}
+
+impl<K: Clone + PartialEq, S: PartialEq, L: LogicVisit<Key = K> + Semantic<Key = K> + Clone> MultilingualBook<S, L> {
+    /// Takes descriptions of `book` as another language, replacing the language if it was added
+    /// before, the primary one included. Recipes are paired by name first, so renumbered ones
+    /// still get their description.
+    pub fn add_language(&mut self, language: impl Into<String>, book: RecipeBook<GenericRecipe<S, L>>) -> Vec<LanguageMismatch> {
+        let language = language.into();
+        let mut mismatches = Vec::new();
+        // index in `book` -> index in the primary language
+        let mut pairs = BTreeMap::new();
+        {
+            let diff = self.book.diff(&book, DiffOptions { match_by_name: true });
+            let mismatch = |index, kind| LanguageMismatch { language: language.clone(), index, kind };
+            for &(index, _) in &diff.removed {
+                mismatches.push(mismatch(index, MismatchKind::Missing));
+            }
+            for &(index, _) in &diff.added {
+                mismatches.push(mismatch(index, MismatchKind::Extra));
+            }
+            for renumbered in &diff.renumbered {
+                pairs.insert(renumbered.new_index, renumbered.old_index);
+                mismatches.push(mismatch(renumbered.old_index, MismatchKind::Renumbered { index: renumbered.new_index }));
+            }
+            for change in &diff.changed {
+                let fields: Vec<_> = change.fields.iter().map(|field| field.field()).filter(|&field| field != RecipeField::Description).collect();
+                if !fields.is_empty() {
+                    mismatches.push(mismatch(change.old_index, MismatchKind::Fields(fields)));
+                }
+            }
+            for &index in book.keys() {
+                if !pairs.contains_key(&index) && !diff.added.iter().any(|&(added, _)| added == index) {
+                    pairs.insert(index, index);
+                }
+            }
+        }
+        mismatches.sort_by_key(|mismatch| mismatch.index);
+
+        let primary = language == self.languages[0];
+        if primary {
+            for recipe in self.book.recipes.values_mut() {
+                recipe.description = None;
+            }
+        }
+        for translations in self.descriptions.values_mut() {
+            translations.remove(&language);
+        }
+        for (index, recipe) in book.recipes {
+            let (Some(&index), Some(description)) = (pairs.get(&index), recipe.description) else {
+                continue;
+            };
+            match self.book.recipes.get_mut(&index) {
+                Some(primary_recipe) if primary => primary_recipe.description = Some(description),
+                _ => {
+                    self.descriptions.entry(index).or_default().insert(language.clone(), description);
+                }
+            }
+        }
+        if !self.languages.contains(&language) {
+            self.languages.push(language);
+        }
+        mismatches
+    }
+}
+
+#[cfg(feature = "msg")]
+impl MultilingualBook<String, crate::logic::LogicNode<String>> {
+    /// Loads FOCRAFT.MSG of the primary language and then of every other one.
+    pub fn from_msg_files<P: AsRef<std::path::Path>>(
+        (language, path): (impl Into<String>, P),
+        others: impl IntoIterator<Item = (impl Into<String>, P)>,
+        encoding: crate::msg::MsgEncoding,
+    ) -> Result<(Self, Vec<LanguageMismatch>), crate::msg::LoadError> {
+        let mut book = Self::new(language, RecipeBook::from_msg_file(path, encoding)?);
+        let mut mismatches = Vec::new();
+        for (language, path) in others {
+            mismatches.extend(book.add_language(language, RecipeBook::from_msg_file(path, encoding)?));
+        }
+        Ok((book, mismatches))
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    #[test]
+    fn shared_requirements() {
+        let mut book = MultilingualBook::new("engl", node_book(&[
+            (1, "PID_A@Knife@@@PID_X 1|PID_Y 1@@PID_A 1@exp 10"),
+            (2, "PID_B@Rope@@@PID_X 1@@PID_B 1@exp 10"),
+            (3, "PID_C@Jet@@@PID_X 1@@PID_C 1@exp 10"),
+        ]));
+        let mismatches = book.add_language("russ", node_book(&[
+            (1, "PID_A@Нож@@@PID_Y 1|PID_X 1@@PID_A 1@exp 10"),
+            (5, "PID_B@Верёвка@@@PID_X 1@@PID_B 1@exp 10"),
+            (3, "PID_C@Джет@@@PID_X 2@@PID_C 1@exp 10"),
+            (4, "PID_D@Нитки@@@PID_X 1@@PID_D 1@exp 10"),
+        ]));
+        assert_eq!(vec![
+            LanguageMismatch { language: "russ".to_owned(), index: 2, kind: MismatchKind::Renumbered { index: 5 } },
+            LanguageMismatch { language: "russ".to_owned(), index: 3, kind: MismatchKind::Fields(vec![RecipeField::Ingredients]) },
+            LanguageMismatch { language: "russ".to_owned(), index: 4, kind: MismatchKind::Extra },
+        ], mismatches);
+        assert_eq!("[russ] #3 differs in ingredients", mismatches[1].to_string());
+
+        assert_eq!(vec!["engl", "russ"], book.languages().collect::<Vec<_>>());
+        assert_eq!(Some(&"Верёвка".to_owned()), book.description(2, "russ"));
+        assert_eq!(vec![("engl", "Knife"), ("russ", "Нож")], book.descriptions(1).map(|(language, text)| (language, text.as_str())).collect::<Vec<_>>());
+        assert_eq!(None, book.description(4, "russ"));
+        assert_eq!(Some(&"Knife".to_owned()), book.book()[&1].description());
+        assert!(book.book().lint(&Default::default()).lints.iter().all(|lint| lint.rule != crate::book::LintRule::EmptyDescription));
+        assert_eq!(vec![1, 2, 3], book.book().keys().copied().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn missing_and_renamed() {
+        let mut book = MultilingualBook::new("engl", node_book(&[
+            (1, "PID_A@Knife@@@PID_X 1@@PID_A 1@exp 10"),
+            (2, "PID_B@Rope@@@PID_X 1@@PID_B 1@exp 10"),
+        ]));
+        let mismatches = book.add_language("germ", node_book(&[(1, "PID_Z@Messer@@@PID_X 1@@PID_A 1@exp 10")]));
+        assert_eq!(vec![
+            LanguageMismatch { language: "germ".to_owned(), index: 1, kind: MismatchKind::Fields(vec![RecipeField::Name]) },
+            LanguageMismatch { language: "germ".to_owned(), index: 2, kind: MismatchKind::Missing },
+        ], mismatches);
+        assert_eq!(Some(&"Messer".to_owned()), book.description(1, "germ"));
+
+        assert_eq!(1, book.add_language("germ", node_book(&[(1, "PID_A@@@@PID_X 1@@PID_A 1@exp 10")])).len());
+        assert_eq!(None, book.description(1, "germ"));
+        assert_eq!(2, book.languages().count());
+
+        book.add_language("engl", node_book(&[(1, "PID_A@Blade@@@PID_X 1@@PID_A 1@exp 10")]));
+        assert_eq!(vec![("engl", "Blade")], book.descriptions(1).map(|(language, text)| (language, text.as_str())).collect::<Vec<_>>());
+        assert_eq!(None, book.book()[&2].description());
+    }
+}
@@ -0,0 +1,22 @@
+/// Internal name patterns, a trailing `*` matches any suffix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NamePatterns(Vec<String>);
+
+impl NamePatterns {
+    /// `PID_ZAPLATKA_CRAFT_*`, the section separators of FO4RP books.
+    pub(crate) fn separators() -> Self {
+        NamePatterns(vec!["PID_ZAPLATKA_CRAFT_*".to_owned()])
+    }
+    pub(crate) fn push(&mut self, pattern: String) {
+        self.0.push(pattern);
+    }
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        self.0.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        })
+    }
+}
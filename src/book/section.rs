@@ -0,0 +1,148 @@
+use crate::recipe::GenericRecipe;
+
+use super::{pattern::NamePatterns, RecipeBook};
+
+/// How separator pseudo-recipes such as `PID_ZAPLATKA_CRAFT_BASIC` are recognized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SeparatorConfig {
+    patterns: NamePatterns,
+    self_crafting: bool,
+}
+
+impl Default for SeparatorConfig {
+    /// `PID_ZAPLATKA_CRAFT_*` names only.
+    fn default() -> Self {
+        SeparatorConfig { patterns: NamePatterns::separators(), self_crafting: false }
+    }
+}
+
+impl SeparatorConfig {
+    /// Internal name of separators, a trailing `*` matches any suffix.
+    pub fn pattern(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+    pub fn clear_patterns(&mut self) -> &mut Self {
+        self.patterns.clear();
+        self
+    }
+    /// Also treat as separators recipes whose only ingredient and only output is one same item.
+    pub fn self_crafting(&mut self, enabled: bool) -> &mut Self {
+        self.self_crafting = enabled;
+        self
+    }
+    pub fn is_separator<S: AsRef<str>, L: crate::logic::LogicVisit>(&self, recipe: &GenericRecipe<S, L>) -> bool
+    where
+        L::Key: PartialEq,
+    {
+        self.patterns.matches(recipe.name.as_ref()) || self.self_crafting && {
+            let (mut ingredients, mut output) = (recipe.ingredients.keys(), recipe.output.keys());
+            match (ingredients.next(), output.next()) {
+                (Some(ingredient), Some(item)) => ingredient == item && ingredients.next().is_none() && output.next().is_none(),
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Recipes under one separator, see [`RecipeBook::sections`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section<'b, R> {
+    /// `None` for the recipes before the first separator.
+    pub separator: Option<(u32, &'b R)>,
+    pub recipes: Vec<(u32, &'b R)>,
+}
+
+impl<R> RecipeBook<R> {
+    pub fn is_separator(&self, index: u32) -> bool {
+        self.separators.contains(&index)
+    }
+    pub fn separators(&self) -> impl Iterator<Item = (&u32, &R)> {
+        self.recipes.iter().filter(|(index, _)| self.separators.contains(index))
+    }
+    /// Every recipe except separators, in MSG order. Analytics and evaluation go through it.
+    pub fn crafts(&self) -> impl Iterator<Item = (&u32, &R)> {
+        self.recipes.iter().filter(|(index, _)| !self.separators.contains(index))
+    }
+    /// Recipes grouped under their separators, in MSG order. A leading section without
+    /// separator is only present when it has recipes.
+    pub fn sections(&self) -> Vec<Section<'_, R>> {
+        let mut sections = vec![Section { separator: None, recipes: Vec::new() }];
+        for (&index, recipe) in &self.recipes {
+            if self.separators.contains(&index) {
+                sections.push(Section { separator: Some((index, recipe)), recipes: Vec::new() });
+            } else if let Some(section) = sections.last_mut() {
+                section.recipes.push((index, recipe));
+            }
+        }
+        if sections[0].recipes.is_empty() {
+            sections.remove(0);
+        }
+        sections
+    }
+    /// Index of the separator heading the recipe at `index`, `None` before the first separator.
+    pub fn section_of(&self, index: u32) -> Option<u32> {
+        self.separators.range(..=index).next_back().copied()
+    }
+}
+
+impl<S: AsRef<str>, L: crate::logic::LogicVisit> RecipeBook<GenericRecipe<S, L>>
+where
+    L::Key: PartialEq,
+{
+    /// Marks separators anew. Books parsed from MSG text already have them marked by the
+    /// default config.
+    pub fn detect_separators(&mut self, config: &SeparatorConfig) {
+        self.separators = self.recipes.iter().filter(|(_, recipe)| config.is_separator(recipe)).map(|(index, _)| *index).collect();
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::{key::KeyMeaning, tests::node_book};
+
+    fn book() -> crate::UserFriendlyRecipeBook {
+        node_book(&[
+            (1, "PID_ROPE@@@@PID_FIBER 3@@PID_ROPE 1@exp 5"),
+            (10, "PID_ZAPLATKA_CRAFT_BASIC@section separator for creating the simplest things@@@PID_ZAPLATKA_CRAFT_BASIC 1@@PID_ZAPLATKA_CRAFT_BASIC 1@exp 0"),
+            (11, "PID_SPEAR@@@@PID_ROPE 1&PID_STICK 1@@PID_SPEAR 1@exp 10"),
+            (12, "PID_CLUB@@@@PID_STICK 1@@PID_CLUB 1@exp 10"),
+            (20, "PID_ZAPLATKA_CRAFT_FOOD@@@@PID_ZAPLATKA_CRAFT_FOOD 1@@PID_ZAPLATKA_CRAFT_FOOD 1@exp 0"),
+            (21, "PID_JERKY@@@@PID_MEAT 4@@PID_JERKY 3@exp 10"),
+        ])
+    }
+
+    #[test]
+    fn sections() {
+        let book = book();
+        let sections: Vec<_> = book.sections().iter()
+            .map(|section| (section.separator.map(|(index, _)| index), section.recipes.iter().map(|(index, _)| *index).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(vec![(None, vec![1]), (Some(10), vec![11, 12]), (Some(20), vec![21])], sections);
+        assert_eq!((None, Some(10), Some(20)), (book.section_of(1), book.section_of(12), book.section_of(21)));
+        assert_eq!(vec![1, 11, 12, 21], book.crafts().map(|(index, _)| *index).collect::<Vec<_>>());
+
+        let mut config = SeparatorConfig::default();
+        config.clear_patterns().self_crafting(true);
+        let mut by_shape = book;
+        by_shape.detect_separators(&config);
+        assert_eq!(vec![10, 20], by_shape.separators().map(|(index, _)| *index).collect::<Vec<_>>());
+        by_shape.detect_separators(config.clear_patterns().self_crafting(false));
+        assert_eq!(0, by_shape.separators().count());
+    }
+
+    #[test]
+    fn separators_excluded() {
+        let book = book();
+        let everything = |_: &String, _: KeyMeaning| 100;
+        assert_eq!(4, book.available(&everything).len());
+        assert!(book.key_index().usages(&"PID_ZAPLATKA_CRAFT_BASIC".to_owned()).is_empty());
+        assert!(book.craft_graph().cycles().is_empty());
+        assert!(book.lint(&{
+            let mut config = crate::book::LintConfig::default();
+            config.clear_exemptions();
+            config
+        }).lints.iter().all(|lint| !book.is_separator(lint.recipe)));
+    }
+}
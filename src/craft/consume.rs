@@ -0,0 +1,286 @@
+use crate::{key::KeyMeaning, logic::{KeyValue, LogicChain, LogicNode}, recipe::GenericRecipe};
+
+use super::{planner::add, Character, Requirement};
+
+/// Most alternatives listed for one expression. Every OR inside an AND multiplies them, so
+/// without a bound a recipe with a few dozen ORs would never finish.
+pub const MAX_ALTERNATIVES: usize = 256;
+
+/// Predicate over a partial alternative, see [`Consumable::alternatives_where`].
+pub type ItemFilter<'f, K> = dyn Fn(&[KeyValue<K>]) -> bool + 'f;
+
+/// Requirement which can be resolved into concrete items to take.
+pub trait Consumable: Requirement {
+    /// Ways to satisfy the expression that `keep` accepts, in expression order, at most
+    /// [`MAX_ALTERNATIVES`]. Repeated keys inside one alternative are summed, since each
+    /// occurrence consumes its own items. `keep` also sees partial alternatives, so it must
+    /// reject every superset of what it rejects, as inventory coverage does.
+    fn alternatives_where(&self, keep: &ItemFilter<'_, Self::Key>) -> Vec<Vec<KeyValue<Self::Key>>>;
+
+    /// Every way to satisfy the expression, see [`Consumable::alternatives_where`].
+    fn alternatives(&self) -> Vec<Vec<KeyValue<Self::Key>>> {
+        self.alternatives_where(&|_| true)
+    }
+
+    /// Cheapest alternative, by `strategy`, that `inventory` covers.
+    fn plan(&self, inventory: &impl Character<Self::Key>, strategy: &impl ConsumptionStrategy<Self::Key>) -> Option<Vec<KeyValue<Self::Key>>> {
+        cheapest(self.alternatives_where(&|items| covers(inventory, items)), inventory, strategy).map(|(_, alternative)| alternative)
+    }
+}
+
+fn covers<K>(inventory: &impl Character<K>, items: &[KeyValue<K>]) -> bool {
+    items.iter().all(|kv| inventory.value(&kv.key, KeyMeaning::Item) >= kv.value)
+}
+
+/// Alternative with its strategy cost.
+type Costed<K> = (u64, Vec<KeyValue<K>>);
+
+fn cheapest<K>(
+    alternatives: Vec<Vec<KeyValue<K>>>,
+    inventory: &impl Character<K>,
+    strategy: &impl ConsumptionStrategy<K>,
+) -> Option<Costed<K>> {
+    let mut best: Option<Costed<K>> = None;
+    for alternative in alternatives {
+        let cost = strategy.cost(&alternative, inventory);
+        if best.as_ref().map_or(true, |(best_cost, _)| cost < *best_cost) {
+            best = Some((cost, alternative));
+        }
+    }
+    best
+}
+
+impl<K: Clone + PartialEq> Consumable for LogicNode<K> {
+    fn alternatives_where(&self, keep: &ItemFilter<'_, K>) -> Vec<Vec<KeyValue<K>>> {
+        match self {
+            LogicNode::KeyValue(kv) => Some(vec![kv.clone()]).into_iter().filter(|alternative| keep(alternative)).collect(),
+            LogicNode::Or(nodes) => {
+                let mut alternatives: Vec<Vec<KeyValue<K>>> = Vec::new();
+                for alternative in nodes.iter().flat_map(|node| node.alternatives_where(keep)) {
+                    if alternatives.len() == MAX_ALTERNATIVES {
+                        break;
+                    }
+                    if !alternatives.iter().any(|known| same_items(known, &alternative)) {
+                        alternatives.push(alternative);
+                    }
+                }
+                alternatives
+            }
+            LogicNode::And(nodes) => nodes.iter().fold(vec![vec![]], |acc, node| {
+                let node_alternatives = node.alternatives_where(keep);
+                acc.iter()
+                    .flat_map(|prefix| node_alternatives.iter().map(move |alternative| merged(prefix, alternative)))
+                    .filter(|alternative| keep(alternative))
+                    .take(MAX_ALTERNATIVES)
+                    .collect()
+            }),
+        }
+    }
+}
+
+impl<K: Clone + PartialEq> Consumable for LogicChain<K> {
+    fn alternatives_where(&self, keep: &ItemFilter<'_, K>) -> Vec<Vec<KeyValue<K>>> {
+        self.clone().logic_nodes::<K>().alternatives_where(keep)
+    }
+}
+
+fn merged<K: Clone + PartialEq>(prefix: &[KeyValue<K>], addition: &[KeyValue<K>]) -> Vec<KeyValue<K>> {
+    let mut items = prefix.to_vec();
+    for kv in addition {
+        match items.iter_mut().find(|item| item.key == kv.key) {
+            Some(item) => item.value = item.value.saturating_add(kv.value),
+            None => items.push(kv.clone()),
+        }
+    }
+    items
+}
+
+fn same_items<K: PartialEq>(a: &[KeyValue<K>], b: &[KeyValue<K>]) -> bool {
+    a.len() == b.len() && a.iter().all(|kv| b.contains(kv))
+}
+
+/// Ranks alternatives which the inventory covers; lower cost wins, ties go to the earlier one.
+pub trait ConsumptionStrategy<K> {
+    fn cost(&self, items: &[KeyValue<K>], inventory: &dyn Character<K>) -> u64;
+}
+
+/// Takes the first alternative the inventory covers.
+pub struct FirstSatisfiable;
+
+impl<K> ConsumptionStrategy<K> for FirstSatisfiable {
+    fn cost(&self, _items: &[KeyValue<K>], _inventory: &dyn Character<K>) -> u64 {
+        0
+    }
+}
+
+/// Minimizes the total of a caller-provided per-item cost.
+pub struct Cheapest<F>(pub F);
+
+impl<K, F: Fn(&K) -> u64> ConsumptionStrategy<K> for Cheapest<F> {
+    fn cost(&self, items: &[KeyValue<K>], _inventory: &dyn Character<K>) -> u64 {
+        items.iter().fold(0u64, |total, kv| total.saturating_add(u64::from(kv.value).saturating_mul((self.0)(&kv.key))))
+    }
+}
+
+/// Prefers spending items the inventory has plenty of, keeping the scarce ones.
+pub struct PreserveRare;
+
+impl<K> ConsumptionStrategy<K> for PreserveRare {
+    fn cost(&self, items: &[KeyValue<K>], inventory: &dyn Character<K>) -> u64 {
+        const SCALE: u64 = 1_000_000;
+        items.iter().fold(0u64, |total, kv| {
+            let stock = u64::from(inventory.value(&kv.key, KeyMeaning::Item)).max(1);
+            total.saturating_add(u64::from(kv.value) * SCALE / stock)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsumeError {
+    /// No alternative of `ingredients` is covered by the inventory.
+    NoAssignment,
+    /// `tools` aren't in the inventory. They are checked and kept out of the ingredients,
+    /// but never consumed.
+    MissingTools,
+}
+
+impl std::fmt::Display for ConsumeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConsumeError::NoAssignment => write!(f, "inventory doesn't cover any alternative of the ingredients"),
+            ConsumeError::MissingTools => write!(f, "required tools are missing"),
+        }
+    }
+}
+
+impl std::error::Error for ConsumeError {}
+
+/// Character's view after some items were taken out of, or reserved in, the inventory.
+pub(crate) struct Spent<'c, C: ?Sized, K> {
+    character: &'c C,
+    /// One total per key.
+    spent: Vec<KeyValue<K>>,
+}
+
+impl<'c, C: ?Sized, K: Clone + PartialEq> Spent<'c, C, K> {
+    pub(crate) fn new(character: &'c C) -> Self {
+        Spent { character, spent: Vec::new() }
+    }
+    pub(crate) fn spend(&mut self, items: &[KeyValue<K>]) {
+        for kv in items {
+            add(&mut self.spent, &kv.key, kv.value);
+        }
+    }
+}
+
+impl<C: Character<K> + ?Sized, K: PartialEq> Character<K> for Spent<'_, C, K> {
+    fn value(&self, key: &K, meaning: KeyMeaning) -> u32 {
+        let value = self.character.value(key, meaning);
+        match meaning {
+            KeyMeaning::Item => value.saturating_sub(self.spent.iter().find(|kv| kv.key == *key).map_or(0, |kv| kv.value)),
+            KeyMeaning::Param => value,
+        }
+    }
+}
+
+impl<S, L: Consumable> GenericRecipe<S, L> where L::Key: Clone + PartialEq {
+    /// Items to remove from the inventory for one craft. Params aren't checked here, see `evaluate`.
+    /// Tools are reserved first, so an item both a tool and an ingredient must be there for
+    /// both; with OR alternatives among the tools the one leaving the cheapest ingredients wins.
+    pub fn plan_consumption(
+        &self,
+        inventory: &impl Character<L::Key>,
+        strategy: &impl ConsumptionStrategy<L::Key>,
+    ) -> Result<Vec<KeyValue<L::Key>>, ConsumeError> {
+        let tool_sets = match &self.tools {
+            Some(tools) => tools.alternatives_where(&|items| covers(inventory, items)),
+            None => vec![vec![]],
+        };
+        if tool_sets.is_empty() {
+            return Err(ConsumeError::MissingTools);
+        }
+        let mut best: Option<Costed<L::Key>> = None;
+        for tool_set in tool_sets {
+            let mut rest = Spent::new(inventory);
+            rest.spend(&tool_set);
+            let alternatives = self.ingredients.alternatives_where(&|items| covers(&rest, items));
+            if let Some((cost, alternative)) = cheapest(alternatives, &rest, strategy) {
+                if best.as_ref().map_or(true, |(best_cost, _)| cost < *best_cost) {
+                    best = Some((cost, alternative));
+                }
+            }
+        }
+        best.map(|(_, alternative)| alternative).ok_or(ConsumeError::NoAssignment)
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::{character, node_book};
+
+    fn names(items: Vec<KeyValue<String>>) -> Vec<(String, u32)> {
+        items.into_iter().map(|kv| (kv.key, kv.value)).collect()
+    }
+
+    #[test]
+    fn alternatives() {
+        let book = node_book(&[(1, "PID_A@@@@PID_X 2&(PID_X 1|PID_Y 1)&PID_Z 1|PID_W 1@@PID_A 1@exp 1")]);
+        let alternatives: Vec<_> = book[&1].ingredients.alternatives().into_iter().map(names).collect();
+        let kv = |key: &str, value| (key.to_owned(), value);
+        assert_eq!(vec![
+            vec![kv("PID_X", 3), kv("PID_Z", 1)],
+            vec![kv("PID_X", 3), kv("PID_W", 1)],
+            vec![kv("PID_X", 2), kv("PID_Y", 1), kv("PID_Z", 1)],
+            vec![kv("PID_X", 2), kv("PID_Y", 1), kv("PID_W", 1)],
+        ], alternatives);
+    }
+
+    #[test]
+    fn strategies() {
+        let book = node_book(&[(1, "PID_MEAT_JERKY@@@@PID_RAD_MEAT 4|PID_MEAT 4&PID_SPIRIT 1@PID_FIREPLACE_TOKEN 1@PID_MEAT_JERKY 3@exp 1")]);
+        let recipe = &book[&1];
+        let inv = character(&[], &[("PID_RAD_MEAT", 4), ("PID_MEAT", 40), ("PID_SPIRIT", 1), ("PID_FIREPLACE_TOKEN", 1)]);
+        let spirit = ("PID_SPIRIT".to_owned(), 1);
+
+        let first = recipe.plan_consumption(&inv, &FirstSatisfiable).unwrap();
+        assert_eq!(vec![("PID_RAD_MEAT".to_owned(), 4), spirit.clone()], names(first));
+        let rare = recipe.plan_consumption(&inv, &PreserveRare).unwrap();
+        assert_eq!(vec![("PID_MEAT".to_owned(), 4), spirit.clone()], names(rare));
+        let cheapest = recipe.plan_consumption(&inv, &Cheapest(|key: &String| if key == "PID_RAD_MEAT" { 1 } else { 10 })).unwrap();
+        assert_eq!(vec![("PID_RAD_MEAT".to_owned(), 4), spirit], names(cheapest));
+
+        let no_tools = character(&[], &[("PID_MEAT", 4), ("PID_SPIRIT", 1)]);
+        assert_eq!(Err(ConsumeError::MissingTools), recipe.plan_consumption(&no_tools, &FirstSatisfiable));
+        let no_meat = character(&[], &[("PID_MEAT", 3), ("PID_SPIRIT", 1), ("PID_FIREPLACE_TOKEN", 1)]);
+        assert_eq!(Err(ConsumeError::NoAssignment), recipe.plan_consumption(&no_meat, &FirstSatisfiable));
+
+        let expensive = Cheapest(|_: &String| u64::MAX);
+        assert!(recipe.plan_consumption(&inv, &expensive).is_ok());
+    }
+
+    #[test]
+    fn tool_also_ingredient() {
+        let book = node_book(&[(1, "PID_SPEAR@@@@PID_KNIFE 1&PID_STICK 1|PID_BRANCH 1@PID_KNIFE 1@PID_SPEAR 1@exp 1")]);
+        let recipe = &book[&1];
+        let one_knife = character(&[], &[("PID_KNIFE", 1), ("PID_STICK", 1)]);
+        assert_eq!(Err(ConsumeError::NoAssignment), recipe.plan_consumption(&one_knife, &FirstSatisfiable));
+        let two_knives = character(&[], &[("PID_KNIFE", 2), ("PID_BRANCH", 1)]);
+        let kv = |key: &str| (key.to_owned(), 1);
+        assert_eq!(vec![kv("PID_KNIFE"), kv("PID_BRANCH")], names(recipe.plan_consumption(&two_knives, &FirstSatisfiable).unwrap()));
+        assert_eq!(Err(ConsumeError::MissingTools), recipe.plan_consumption(&character(&[], &[("PID_STICK", 1)]), &FirstSatisfiable));
+    }
+
+    #[test]
+    fn bounded_alternatives() {
+        let ors: Vec<_> = (0..40).map(|i| format!("PID_A{i} 1|PID_B{i} 1")).collect();
+        let line = format!("PID_X@@@@{}@@PID_X 1@exp 1", ors.join("&"));
+        let book = node_book(&[(1, &line)]);
+        assert_eq!(MAX_ALTERNATIVES, book[&1].ingredients.alternatives().len());
+        let mut items: Vec<(String, u32)> = (0..40).map(|i| (format!("PID_B{i}"), 1)).collect();
+        items.push(("PID_A39".to_owned(), 1));
+        let items: Vec<(&str, u32)> = items.iter().map(|(key, count)| (key.as_str(), *count)).collect();
+        let planned = book[&1].plan_consumption(&character(&[], &items), &FirstSatisfiable).unwrap();
+        assert_eq!(40, planned.len());
+    }
+}
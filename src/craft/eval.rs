@@ -0,0 +1,190 @@
+use crate::{key::KeyMeaning, logic::{KeyValue, LogicChain, LogicNode, Logical}, recipe::{GenericRecipe, RecipeField}};
+
+/// Caller's view of a character: param values for `KeyMeaning::Param` keys and item counts for
+/// `KeyMeaning::Item` keys.
+pub trait Character<K> {
+    fn value(&self, key: &K, meaning: KeyMeaning) -> u32;
+}
+
+impl<K, F: Fn(&K, KeyMeaning) -> u32> Character<K> for F {
+    fn value(&self, key: &K, meaning: KeyMeaning) -> u32 {
+        self(key, meaning)
+    }
+}
+
+/// Requirement expression which can be checked key by key.
+pub trait Requirement {
+    type Key;
+    /// Calls `check` for every key-value, without short-circuiting, and combines the results.
+    fn evaluate_with<'a>(&'a self, check: &mut dyn FnMut(&'a KeyValue<Self::Key>) -> bool) -> bool;
+    fn is_satisfied(&self, character: &impl Character<Self::Key>, meaning: KeyMeaning) -> bool {
+        self.evaluate_with(&mut |kv| character.value(&kv.key, meaning) >= kv.value)
+    }
+}
+
+impl<K> Requirement for LogicNode<K> {
+    type Key = K;
+    fn evaluate_with<'a>(&'a self, check: &mut dyn FnMut(&'a KeyValue<K>) -> bool) -> bool {
+        let (nodes, and) = match self {
+            LogicNode::And(nodes) => (nodes, true),
+            LogicNode::Or(nodes) => (nodes, false),
+            LogicNode::KeyValue(kv) => return check(kv),
+        };
+        let mut passed = and;
+        for node in nodes {
+            if and {
+                passed &= node.evaluate_with(check);
+            } else {
+                passed |= node.evaluate_with(check);
+            }
+        }
+        passed
+    }
+}
+
+impl<K> Requirement for LogicChain<K> {
+    type Key = K;
+    fn evaluate_with<'a>(&'a self, check: &mut dyn FnMut(&'a KeyValue<K>) -> bool) -> bool {
+        // OR binds tighter than AND, same as in `logic_nodes`
+        let mut and_section = true;
+        let mut or_section = check(&self.first);
+        for (logical, kv) in &self.rest {
+            let passed = check(kv);
+            match logical {
+                Logical::Or => or_section |= passed,
+                Logical::And => {
+                    and_section &= or_section;
+                    or_section = passed;
+                }
+            }
+        }
+        and_section && or_section
+    }
+}
+
+/// Single key-value compared against the character.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check<'r, K> {
+    pub key: &'r K,
+    pub required: u32,
+    pub actual: u32,
+}
+
+impl<'r, K> Check<'r, K> {
+    pub fn passed(&self) -> bool {
+        self.actual >= self.required
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockTrace<'r, K> {
+    pub field: RecipeField,
+    pub passed: bool,
+    pub checks: Vec<Check<'r, K>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict<'r, K> {
+    /// `params_to_see` is satisfied, so the recipe shows up in the craft list.
+    pub visible: bool,
+    /// Visible, and every of `params_to_craft`, `ingredients` and `tools` is satisfied.
+    pub craftable: bool,
+    /// One entry per present requirement block, in recipe order.
+    pub blocks: Vec<BlockTrace<'r, K>>,
+}
+
+impl<'r, K> Verdict<'r, K> {
+    pub fn block(&self, field: RecipeField) -> Option<&BlockTrace<'r, K>> {
+        self.blocks.iter().find(|block| block.field == field)
+    }
+    /// Failing key-values of the blocks which failed, i.e. which kept the recipe from being
+    /// crafted or seen. Inside such a block they may include OR branches another branch covered.
+    pub fn failed_checks(&self) -> impl Iterator<Item = (RecipeField, &Check<'r, K>)> {
+        self.blocks.iter().filter(|block| !block.passed)
+            .flat_map(|block| block.checks.iter().filter(|check| !check.passed()).map(move |check| (block.field, check)))
+    }
+}
+
+impl<S, L: Requirement> GenericRecipe<S, L> {
+    pub fn evaluate<'r>(&'r self, character: &impl Character<L::Key>) -> Verdict<'r, L::Key> {
+        let blocks: Vec<_> = [
+            (RecipeField::ParamsToSee, self.params_to_see.as_ref(), KeyMeaning::Param),
+            (RecipeField::ParamsToCraft, self.params_to_craft.as_ref(), KeyMeaning::Param),
+            (RecipeField::Ingredients, Some(&self.ingredients), KeyMeaning::Item),
+            (RecipeField::Tools, self.tools.as_ref(), KeyMeaning::Item),
+        ].into_iter().filter_map(|(field, logic, meaning)| {
+            let logic = logic?;
+            let mut checks = Vec::new();
+            let passed = logic.evaluate_with(&mut |kv| {
+                let check = Check { key: &kv.key, required: kv.value, actual: character.value(&kv.key, meaning) };
+                let passed = check.passed();
+                checks.push(check);
+                passed
+            });
+            Some(BlockTrace { field, passed, checks })
+        }).collect();
+        let passed = |field| blocks.iter().find(|block: &&BlockTrace<_>| block.field == field).map_or(true, |block| block.passed);
+        let visible = passed(RecipeField::ParamsToSee);
+        let craftable = visible && [RecipeField::ParamsToCraft, RecipeField::Ingredients, RecipeField::Tools].into_iter().all(passed);
+        Verdict { visible, craftable, blocks }
+    }
+    pub fn is_visible(&self, character: &impl Character<L::Key>) -> bool {
+        self.params_to_see.as_ref().map_or(true, |logic| logic.is_satisfied(character, KeyMeaning::Param))
+    }
+    pub fn is_craftable(&self, character: &impl Character<L::Key>) -> bool {
+        self.is_visible(character)
+            && self.params_to_craft.as_ref().map_or(true, |logic| logic.is_satisfied(character, KeyMeaning::Param))
+            && self.ingredients.is_satisfied(character, KeyMeaning::Item)
+            && self.tools.as_ref().map_or(true, |logic| logic.is_satisfied(character, KeyMeaning::Item))
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    fn character() -> impl Fn(&String, KeyMeaning) -> u32 {
+        |key, meaning| match (key.as_str(), meaning) {
+            ("SK_REPAIR", KeyMeaning::Param) => 60,
+            ("SK_SCIENCE", KeyMeaning::Param) => 100,
+            ("PID_BOTTLE_EMPTY", KeyMeaning::Item) => 5,
+            ("PID_KNIFE", KeyMeaning::Item) => 1,
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn evaluate_recipe() {
+        let book = node_book(&[
+            (1, "PID_EMPTY_JET@@SK_REPAIR 50@SK_REPAIR 100|SK_SCIENCE 100@PID_BOTTLE_EMPTY 5@PID_KNIFE 1&PID_LIGHTER 1@PID_EMPTY_JET 5@exp 10"),
+            (2, "PID_EMPTY_JET@@SK_REPAIR 70@@PID_BOTTLE_EMPTY 5@@PID_EMPTY_JET 5@exp 10"),
+            (3, "PID_EMPTY_JET@@@SK_REPAIR 100|SK_SCIENCE 100@PID_BOTTLE_EMPTY 5@PID_KNIFE 1@PID_EMPTY_JET 5@exp 10"),
+        ]);
+        let verdict = book[&1].evaluate(&character());
+        assert!(verdict.visible);
+        assert!(!verdict.craftable);
+        assert_eq!(vec![true, true, true, false], verdict.blocks.iter().map(|block| block.passed).collect::<Vec<_>>());
+        let failed: Vec<_> = verdict.failed_checks().map(|(field, check)| (field, check.key.as_str(), check.actual)).collect();
+        assert_eq!(vec![(RecipeField::Tools, "PID_LIGHTER", 0)], failed);
+        assert_eq!(2, verdict.block(RecipeField::ParamsToCraft).unwrap().checks.len());
+
+        assert!(!book[&2].is_visible(&character()));
+        assert!(!book[&2].evaluate(&character()).craftable);
+        assert!(book[&3].is_craftable(&character()));
+        assert!(book[&3].evaluate(&character()).craftable);
+    }
+
+    #[test]
+    fn chain_and_node_agree() {
+        let chain = LogicChain {
+            first: KeyValue { key: "A", value: 1 },
+            rest: vec![(Logical::Or, KeyValue { key: "B", value: 1 }), (Logical::And, KeyValue { key: "C", value: 1 })],
+        };
+        let node: LogicNode<&str> = chain.clone().logic_nodes();
+        for have in [[0, 0, 0], [1, 0, 1], [0, 1, 1], [1, 1, 0], [0, 0, 1]] {
+            let character = |key: &&str, _| have[(key.as_bytes()[0] - b'A') as usize];
+            assert_eq!(chain.is_satisfied(&character, KeyMeaning::Item), node.is_satisfied(&character, KeyMeaning::Item), "{have:?}");
+        }
+    }
+}
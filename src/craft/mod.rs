@@ -0,0 +1,19 @@
+mod eval;
+mod consume;
+mod semantic;
+mod planner;
+mod query;
+mod simulate;
+mod script;
+
+pub use self::{
+    eval::{Character, Check, Requirement, BlockTrace, Verdict},
+    consume::{Consumable, ConsumptionStrategy, ItemFilter, MAX_ALTERNATIVES, FirstSatisfiable, Cheapest, PreserveRare, ConsumeError},
+    semantic::{Semantic, Assignment, Counterexample},
+    planner::{PlannedCraft, ProductionPlan, Shortfall},
+    query::Availability,
+    simulate::{CraftState, SimCharacter, Change, Transaction, SimulateError, Simulator},
+    script::{ScriptCall, ScriptHandler, UnhandledScript, ScriptRegistry},
+};
+
+pub(crate) use self::planner::add;
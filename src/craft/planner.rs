@@ -0,0 +1,311 @@
+use std::cell::Cell;
+
+use crate::{book::RecipeBook, key::KeyMeaning, logic::{KeyValue, LogicVisit}, recipe::GenericRecipe};
+
+use super::{Character, Consumable};
+
+/// Recipe executed `crafts` times in a row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedCraft<K> {
+    pub recipe: u32,
+    pub crafts: u32,
+    pub consumed: Vec<KeyValue<K>>,
+    pub produced: Vec<KeyValue<K>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductionPlan<K> {
+    /// Crafts in execution order, each after the crafts making its ingredients and tools.
+    pub crafts: Vec<PlannedCraft<K>>,
+    /// Items gained beyond the targets: byproducts, surplus output and crafted tools.
+    pub leftovers: Vec<KeyValue<K>>,
+}
+
+/// Goal isn't reachable from the inventory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shortfall<K> {
+    /// Items to obtain some other way, e.g. raw resources or items behind missing skills.
+    pub missing: Vec<KeyValue<K>>,
+    /// Plan which works once `missing` is added to the inventory.
+    pub plan: ProductionPlan<K>,
+}
+
+#[derive(Clone)]
+struct State<K> {
+    /// Current counts of the items touched so far, the rest is still as the character has them.
+    stock: Vec<KeyValue<K>>,
+    crafts: Vec<PlannedCraft<K>>,
+    missing: Vec<KeyValue<K>>,
+}
+
+impl<K: Clone + PartialEq> State<K> {
+    fn get(&self, key: &K, character: &impl Character<K>) -> u32 {
+        self.stock.iter().find(|kv| kv.key == *key).map_or_else(|| character.value(key, KeyMeaning::Item), |kv| kv.value)
+    }
+    fn missing(&self, key: &K) -> u32 {
+        self.missing.iter().find(|kv| kv.key == *key).map_or(0, |kv| kv.value)
+    }
+    fn set(&mut self, key: &K, value: u32) {
+        match self.stock.iter_mut().find(|kv| kv.key == *key) {
+            Some(kv) => kv.value = value,
+            None => self.stock.push(KeyValue::new(key.clone(), value)),
+        }
+    }
+}
+
+/// Trial crafts one [`RecipeBook::plan_production`] call may search through.
+const MAX_ATTEMPTS: u32 = 10_000;
+
+struct Planner<'b, S, L, C> {
+    book: &'b RecipeBook<GenericRecipe<S, L>>,
+    character: &'b C,
+    /// Trial crafts left, each one clones the state.
+    attempts: Cell<u32>,
+}
+
+impl<K, S, L, C> Planner<'_, S, L, C>
+where
+    K: Clone + PartialEq,
+    L: LogicVisit<Key = K> + Consumable<Key = K>,
+    C: Character<K>,
+{
+    /// Takes `quantity` of `item` out of the stock, crafting what's missing. Strict mode fails
+    /// instead of recording missing items, leaving `state` in an unspecified shape. Once the
+    /// attempts run out, candidates aren't searched anymore and lenient mode takes the first one.
+    fn acquire(&self, item: &K, quantity: u32, state: &mut State<K>, stack: &mut Vec<K>, lenient: bool) -> bool {
+        let have = state.get(item, self.character);
+        let used = have.min(quantity);
+        state.set(item, have - used);
+        let needed = quantity - used;
+        if needed == 0 {
+            return true;
+        }
+        let candidates: Vec<(u32, &GenericRecipe<S, L>, u32)> = if stack.contains(item) {
+            Vec::new()
+        } else {
+            self.book.crafts().filter_map(|(&index, recipe)| {
+                let yields: u32 = recipe.output.key_values().filter(|kv| kv.key == *item).map(|kv| kv.value).sum();
+                let skilled = [&recipe.params_to_see, &recipe.params_to_craft]
+                    .into_iter()
+                    .all(|params| params.as_ref().map_or(true, |params| params.is_satisfied(self.character, KeyMeaning::Param)));
+                (yields > 0 && skilled).then_some((index, recipe, yields))
+            }).collect()
+        };
+        stack.push(item.clone());
+        let mut done = false;
+        'search: for &(index, recipe, yields) in &candidates {
+            for alternative in recipe.ingredients.alternatives() {
+                let Some(attempts) = self.attempts.get().checked_sub(1) else {
+                    break 'search;
+                };
+                self.attempts.set(attempts);
+                let mut trial = state.clone();
+                if self.craft(index, recipe, yields, &alternative, needed, &mut trial, stack, false) {
+                    *state = trial;
+                    done = true;
+                    break 'search;
+                }
+            }
+        }
+        if !done && lenient {
+            if let Some(&(index, recipe, yields)) = candidates.first() {
+                let alternative = recipe.ingredients.alternatives().swap_remove(0);
+                done = self.craft(index, recipe, yields, &alternative, needed, state, stack, true);
+            } else {
+                add(&mut state.missing, item, needed);
+                done = true;
+            }
+        }
+        stack.pop();
+        done
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn craft(
+        &self,
+        index: u32,
+        recipe: &GenericRecipe<S, L>,
+        yields: u32,
+        alternative: &[KeyValue<K>],
+        needed: u32,
+        state: &mut State<K>,
+        stack: &mut Vec<K>,
+        lenient: bool,
+    ) -> bool {
+        // tools aren't consumed, but they are taken out of the stock until the craft is done,
+        // so acquiring the ingredients can't use them up
+        let mut tools = Vec::new();
+        if let Some(logic) = &recipe.tools {
+            let mut alternatives = logic.alternatives();
+            // ones already in stock first, so nothing is crafted needlessly
+            alternatives.sort_by_key(|alternative| !alternative.iter().all(|kv| state.get(&kv.key, self.character) >= kv.value));
+            let mut found = false;
+            for alternative in &alternatives {
+                let Some(attempts) = self.attempts.get().checked_sub(1) else {
+                    break;
+                };
+                self.attempts.set(attempts);
+                let mut trial = state.clone();
+                if alternative.iter().all(|kv| self.acquire(&kv.key, kv.value, &mut trial, stack, false)) {
+                    *state = trial;
+                    tools.clone_from(alternative);
+                    found = true;
+                    break;
+                }
+            }
+            if !found {
+                let Some(alternative) = alternatives.into_iter().next().filter(|_| lenient) else {
+                    return false;
+                };
+                for kv in alternative {
+                    let missing = state.missing(&kv.key);
+                    self.acquire(&kv.key, kv.value, state, stack, true);
+                    // only put back what was obtained, not what is recorded as missing
+                    let obtained = kv.value.saturating_sub(state.missing(&kv.key) - missing);
+                    tools.push(KeyValue::new(kv.key, obtained));
+                }
+            }
+        }
+        let crafts = needed.div_ceil(yields);
+        let consumed: Vec<KeyValue<K>> = alternative.iter().map(|kv| KeyValue::new(kv.key.clone(), kv.value.saturating_mul(crafts))).collect();
+        for kv in &consumed {
+            if !self.acquire(&kv.key, kv.value, state, stack, lenient) {
+                return false;
+            }
+        }
+        let produced: Vec<KeyValue<K>> = recipe.output.key_values().map(|kv| KeyValue::new(kv.key.clone(), kv.value.saturating_mul(crafts))).collect();
+        for kv in &produced {
+            let have = state.get(&kv.key, self.character);
+            state.set(&kv.key, have.saturating_add(kv.value));
+        }
+        let item = stack.last().expect("crafting is always for an acquired item");
+        let have = state.get(item, self.character);
+        state.set(&item.clone(), have - needed);
+        for kv in &tools {
+            let have = state.get(&kv.key, self.character);
+            state.set(&kv.key, have.saturating_add(kv.value));
+        }
+        state.crafts.push(PlannedCraft { recipe: index, crafts, consumed, produced });
+        true
+    }
+}
+
+/// Adds to the total of `key`, keeping one entry per key.
+pub(crate) fn add<K: Clone + PartialEq>(items: &mut Vec<KeyValue<K>>, key: &K, value: u32) {
+    match items.iter_mut().find(|kv| kv.key == *key) {
+        Some(kv) => kv.value = kv.value.saturating_add(value),
+        None => items.push(KeyValue::new(key.clone(), value)),
+    }
+}
+
+impl<K: Clone + PartialEq, S, L: LogicVisit<Key = K> + Consumable<Key = K>> RecipeBook<GenericRecipe<S, L>> {
+    /// Crafts turning the character's inventory into `targets`, using items already owned first.
+    /// Recipes the character lacks the params for are skipped. Every recipe and alternative may
+    /// be tried for each intermediate, up to a fixed number of trial crafts per call; past it
+    /// the first candidates are taken, so a deep book with many ORs may get a worse plan.
+    pub fn plan_production(&self, character: &impl Character<K>, targets: &[KeyValue<K>]) -> Result<ProductionPlan<K>, Shortfall<K>> {
+        let planner = Planner { book: self, character, attempts: Cell::new(MAX_ATTEMPTS) };
+        let mut state = State { stock: Vec::new(), crafts: Vec::new(), missing: Vec::new() };
+        for target in targets {
+            let mut trial = state.clone();
+            if planner.acquire(&target.key, target.value, &mut trial, &mut Vec::new(), false) {
+                state = trial;
+            } else {
+                planner.acquire(&target.key, target.value, &mut state, &mut Vec::new(), true);
+            }
+        }
+        let mut leftovers = Vec::new();
+        for kv in &state.stock {
+            let before = character.value(&kv.key, KeyMeaning::Item);
+            if kv.value > before {
+                leftovers.push(KeyValue::new(kv.key.clone(), kv.value - before));
+            }
+        }
+        let plan = ProductionPlan { crafts: state.crafts, leftovers };
+        if state.missing.is_empty() {
+            Ok(plan)
+        } else {
+            Err(Shortfall { missing: state.missing, plan })
+        }
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::{character, node_book, pairs};
+
+    fn book() -> crate::UserFriendlyRecipeBook {
+        node_book(&[
+            (1, "PID_JETPACK@@@SK_SCIENCE 50@PID_EMPTY_JET 2&PID_METAL 1@PID_WRENCH 1@PID_JETPACK 1@exp 10"),
+            (2, "PID_EMPTY_JET@@@@PID_METAL 1@@PID_EMPTY_JET 5@exp 10"),
+            (3, "PID_METAL@@@@PID_ORE 3|PID_SCRAP 1@@PID_METAL 1&PID_SLAG 1@exp 10"),
+            (4, "PID_WRENCH@@@@PID_METAL 1@@PID_WRENCH 1@exp 10"),
+            (5, "PID_METAL@@@SK_REPAIR 200@PID_DIRT 1@@PID_METAL 10@exp 10"),
+        ])
+    }
+
+    #[test]
+    fn ordered_plan() {
+        let plan = book().plan_production(&character(&[("SK_SCIENCE", 100)], &[("PID_ORE", 30), ("PID_SCRAP", 1)]), &[KeyValue::new("PID_JETPACK".to_owned(), 3)]).unwrap();
+        let crafts: Vec<_> = plan.crafts.iter().map(|craft| (craft.recipe, craft.crafts)).collect();
+        assert_eq!(vec![(3, 1), (4, 1), (3, 2), (2, 2), (3, 3), (1, 3)], crafts);
+        assert_eq!(vec![("PID_ORE", 3)], pairs(&plan.crafts[0].consumed));
+        assert_eq!(vec![("PID_WRENCH", 1), ("PID_SLAG", 6), ("PID_EMPTY_JET", 4)], pairs(&plan.leftovers));
+    }
+
+    #[test]
+    fn shortfall() {
+        let shortfall = book().plan_production(&character(&[("SK_SCIENCE", 100)], &[("PID_ORE", 10), ("PID_WRENCH", 1)]), &[KeyValue::new("PID_JETPACK".to_owned(), 3)]).unwrap_err();
+        assert_eq!(vec![("PID_ORE", 5)], pairs(&shortfall.missing));
+        assert_eq!(Some(&1), shortfall.plan.crafts.last().map(|craft| &craft.recipe));
+    }
+
+    #[test]
+    fn tool_alternatives() {
+        let book = node_book(&[
+            (1, "PID_LOG@@@@PID_TREE 1@PID_KNIFE 1|PID_AXE 1@PID_LOG 1@exp 10"),
+            (2, "PID_AXE@@@@PID_ORE 2@@PID_AXE 1@exp 10"),
+        ]);
+        let plan = book.plan_production(&character(&[], &[("PID_TREE", 1), ("PID_ORE", 2)]), &[KeyValue::new("PID_LOG".to_owned(), 1)]).unwrap();
+        assert_eq!(vec![2, 1], plan.crafts.iter().map(|craft| craft.recipe).collect::<Vec<_>>());
+        assert_eq!(vec![("PID_AXE", 1)], pairs(&plan.leftovers));
+
+        let shortfall = book.plan_production(&character(&[], &[("PID_TREE", 1)]), &[KeyValue::new("PID_LOG".to_owned(), 1)]).unwrap_err();
+        assert_eq!(vec![("PID_KNIFE", 1)], pairs(&shortfall.missing));
+        assert!(shortfall.plan.leftovers.is_empty());
+    }
+
+    #[test]
+    fn tools_reserved() {
+        let book = node_book(&[
+            (1, "PID_X@@@@PID_PART 1@PID_WRENCH 1@PID_X 1@exp 10"),
+            (2, "PID_PART@@@@PID_WRENCH 1@@PID_PART 1@exp 10"),
+        ]);
+        let shortfall = book.plan_production(&character(&[], &[("PID_WRENCH", 1)]), &[KeyValue::new("PID_X".to_owned(), 1)]).unwrap_err();
+        assert_eq!(vec![("PID_WRENCH", 1)], pairs(&shortfall.missing));
+        let plan = book.plan_production(&character(&[], &[("PID_WRENCH", 2)]), &[KeyValue::new("PID_X".to_owned(), 1)]).unwrap();
+        assert_eq!(vec![2, 1], plan.crafts.iter().map(|craft| craft.recipe).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn cycle_with_escape() {
+        let book = node_book(&[
+            (1, "PID_A@@@@PID_B 1|PID_ORE 2@@PID_A 1@exp 10"),
+            (2, "PID_B@@@@PID_A 1@@PID_B 1@exp 10"),
+        ]);
+        let plan = book.plan_production(&character(&[], &[("PID_ORE", 2)]), &[KeyValue::new("PID_A".to_owned(), 1)]).unwrap();
+        assert_eq!(vec![("PID_ORE", 2)], pairs(&plan.crafts[0].consumed));
+        let shortfall = book.plan_production(&character(&[], &[]), &[KeyValue::new("PID_B".to_owned(), 1)]).unwrap_err();
+        assert_eq!(vec![("PID_B", 1)], pairs(&shortfall.missing));
+    }
+
+    #[test]
+    fn bounded_search() {
+        let lines: Vec<_> = (0..24).flat_map(|n| ["I", "J"].map(|item| (n * 2 + u32::from(item == "J"), format!("PID_{item}{n}@@@@PID_I{} 1|PID_J{} 1@@PID_{item}{n} 1@exp 1", n + 1, n + 1)))).collect();
+        let book = node_book(&lines.iter().map(|(index, line)| (*index, line.as_str())).collect::<Vec<_>>());
+        let shortfall = book.plan_production(&character(&[], &[]), &[KeyValue::new("PID_I0".to_owned(), 1)]).unwrap_err();
+        assert_eq!(vec![("PID_I24", 1)], pairs(&shortfall.missing));
+        assert_eq!(24, shortfall.plan.crafts.len());
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{book::RecipeBook, key::KeyMeaning, recipe::GenericRecipe};
+
+use super::{consume::Spent, Character, Consumable, FirstSatisfiable, PreserveRare};
+
+impl<S, L: Consumable> GenericRecipe<S, L> where L::Key: Clone + PartialEq {
+    /// Crafts in a row before the inventory runs out; 0 if the recipe isn't craftable at all.
+    /// Tools are only checked once, since they aren't consumed, and the first alternative of them
+    /// the inventory covers stays reserved. A single alternative of ingredients is divided out
+    /// directly; with OR alternatives each craft spends the alternative leaving the most of the
+    /// scarce items, a greedy choice which may miss a better mix and takes a step per craft.
+    /// `u32::MAX` if a craft consumes nothing.
+    pub fn max_crafts(&self, character: &impl Character<L::Key>) -> u32 {
+        if !self.is_craftable(character) {
+            return 0;
+        }
+        let mut inventory = Spent::new(character);
+        if let Some(tools) = &self.tools {
+            if let Some(tool_set) = tools.plan(character, &FirstSatisfiable) {
+                inventory.spend(&tool_set);
+            }
+        }
+        let mut alternatives = self.ingredients.alternatives();
+        if alternatives.len() == 1 {
+            return alternatives.remove(0).iter()
+                .filter(|kv| kv.value > 0)
+                .map(|kv| inventory.value(&kv.key, KeyMeaning::Item) / kv.value)
+                .min()
+                .unwrap_or(u32::MAX);
+        }
+        let mut crafts = 0u32;
+        while let Some(items) = self.ingredients.plan(&inventory, &PreserveRare) {
+            if items.iter().all(|kv| kv.value == 0) {
+                return u32::MAX;
+            }
+            inventory.spend(&items);
+            crafts += 1;
+        }
+        crafts
+    }
+}
+
+/// Visible recipe of a book, see [`RecipeBook::available`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Availability<'r, R> {
+    pub index: u32,
+    pub recipe: &'r R,
+    /// See [`GenericRecipe::max_crafts`], 0 for a recipe which is only visible.
+    pub max_crafts: u32,
+}
+
+impl<S, L: Consumable> RecipeBook<GenericRecipe<S, L>> where L::Key: Clone + PartialEq {
+    /// Recipes shown to the character in MSG order, separators left out.
+    pub fn available(&self, character: &impl Character<L::Key>) -> Vec<Availability<'_, GenericRecipe<S, L>>> {
+        self.crafts()
+            .filter(|(_, recipe)| recipe.is_visible(character))
+            .map(|(&index, recipe)| Availability { index, recipe, max_crafts: recipe.max_crafts(character) })
+            .collect()
+    }
+    /// Recipes the character can craft right now, in MSG order.
+    pub fn craftable_now(&self, character: &impl Character<L::Key>) -> Vec<Availability<'_, GenericRecipe<S, L>>> {
+        let mut available = self.available(character);
+        available.retain(|availability| availability.max_crafts > 0);
+        available
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use crate::tests::{character, node_book};
+
+    #[test]
+    fn craftable_now() {
+        let book = node_book(&[
+            (1, "PID_JERKY@@@@PID_MEAT 4|PID_RAD_MEAT 4&PID_SALT 1@PID_FIRE 1@PID_JERKY 3@exp 10"),
+            (2, "PID_STEW@@SK_OUTDOORSMAN 20@SK_OUTDOORSMAN 80@PID_MEAT 1@@PID_STEW 1@exp 10"),
+            (3, "PID_BOMB@@SK_TRAPS 50@@PID_SALT 1@@PID_BOMB 1@exp 10"),
+            (4, "PID_SALT@@@@PID_WATER 2@PID_FIRE 1@PID_SALT 1@exp 10"),
+        ]);
+        let inventory = character(&[("SK_OUTDOORSMAN", 50)], &[("PID_MEAT", 9), ("PID_RAD_MEAT", 5), ("PID_SALT", 5), ("PID_FIRE", 1), ("PID_WATER", 1)]);
+        let available: Vec<_> = book.available(&inventory).iter().map(|availability| (availability.index, availability.max_crafts)).collect();
+        assert_eq!(vec![(1, 3), (2, 0), (4, 0)], available);
+        assert_eq!(vec![1], book.craftable_now(&inventory).iter().map(|availability| availability.index).collect::<Vec<_>>());
+
+        let no_salt = character(&[("SK_OUTDOORSMAN", 50)], &[("PID_MEAT", 9), ("PID_SALT", 1), ("PID_FIRE", 1)]);
+        assert_eq!(1, book[&1].max_crafts(&no_salt));
+        assert_eq!(0, book[&1].max_crafts(&character(&[("SK_OUTDOORSMAN", 50)], &[("PID_MEAT", 9), ("PID_SALT", 5)])));
+        assert_eq!(3, book[&4].max_crafts(&character(&[], &[("PID_WATER", 7), ("PID_FIRE", 1)])));
+        assert_eq!(u32::MAX / 2, book[&4].max_crafts(&character(&[], &[("PID_WATER", u32::MAX), ("PID_FIRE", 1)])));
+    }
+}
@@ -0,0 +1,140 @@
+use std::{collections::{BTreeMap, HashMap}, fmt::Display};
+
+use crate::{book::RecipeBook, recipe::{GenericRecipe, SideEffect}};
+
+use super::CraftState;
+
+/// `script module@function` side effect being run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScriptCall<'a> {
+    pub module: &'a str,
+    pub function: &'a str,
+}
+
+impl Display for ScriptCall<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.module, self.function)
+    }
+}
+
+impl<S: AsRef<str>> SideEffect<S> {
+    /// `None` for experience. Numeric books only say `script`, which gives empty names.
+    pub fn script_call(&self) -> Option<ScriptCall<'_>> {
+        match self {
+            SideEffect::Script { module, function } => Some(ScriptCall { module: module.as_ref(), function: function.as_ref() }),
+            SideEffect::Experience(_) => None,
+        }
+    }
+}
+
+/// Rust stand-in for a server script. Changes made through `state` are part of the craft's
+/// transaction, and an error rolls the whole craft back.
+pub trait ScriptHandler<K> {
+    fn run(&mut self, call: ScriptCall<'_>, state: &mut dyn CraftState<K>) -> Result<(), String>;
+}
+
+impl<K, F: FnMut(ScriptCall<'_>, &mut dyn CraftState<K>) -> Result<(), String>> ScriptHandler<K> for F {
+    fn run(&mut self, call: ScriptCall<'_>, state: &mut dyn CraftState<K>) -> Result<(), String> {
+        self(call, state)
+    }
+}
+
+/// Script referenced by a book without a bound handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnhandledScript {
+    pub module: String,
+    pub function: String,
+    /// MSG indices of the recipes using it.
+    pub recipes: Vec<u32>,
+}
+
+/// Handlers for `script module@function` side effects, see [`super::Simulator::with_scripts`].
+pub struct ScriptRegistry<'h, K> {
+    handlers: HashMap<(String, String), Box<dyn ScriptHandler<K> + 'h>>,
+    default: Option<Box<dyn ScriptHandler<K> + 'h>>,
+}
+
+impl<K> Default for ScriptRegistry<'_, K> {
+    fn default() -> Self {
+        ScriptRegistry { handlers: HashMap::new(), default: None }
+    }
+}
+
+impl<'h, K> ScriptRegistry<'h, K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn bind(&mut self, module: &str, function: &str, handler: impl ScriptHandler<K> + 'h) -> &mut Self {
+        self.handlers.insert((module.to_owned(), function.to_owned()), Box::new(handler));
+        self
+    }
+    /// Runs scripts without their own handler. Without a default those fail the craft.
+    pub fn bind_default(&mut self, handler: impl ScriptHandler<K> + 'h) -> &mut Self {
+        self.default = Some(Box::new(handler));
+        self
+    }
+    /// Bound to a handler of its own, the default doesn't count.
+    pub fn is_bound(&self, call: ScriptCall<'_>) -> bool {
+        self.handlers.contains_key(&(call.module.to_owned(), call.function.to_owned()))
+    }
+    /// Own handler, or the default one.
+    pub(crate) fn handler_mut(&mut self, call: ScriptCall<'_>) -> Option<&mut (dyn ScriptHandler<K> + 'h)> {
+        let key = (call.module.to_owned(), call.function.to_owned());
+        match self.handlers.get_mut(&key) {
+            Some(handler) => Some(handler.as_mut()),
+            None => self.default.as_deref_mut(),
+        }
+    }
+    /// Scripts in `book` without a handler of their own, sorted by module and function.
+    pub fn unhandled_scripts<S: AsRef<str>, L>(&self, book: &RecipeBook<GenericRecipe<S, L>>) -> Vec<UnhandledScript> {
+        let mut unhandled: BTreeMap<(&str, &str), Vec<u32>> = BTreeMap::new();
+        for (&index, recipe) in book.crafts() {
+            if let Some(call) = recipe.side_effect.script_call().filter(|call| !self.is_bound(*call)) {
+                unhandled.entry((call.module, call.function)).or_default().push(index);
+            }
+        }
+        unhandled.into_iter()
+            .map(|((module, function), recipes)| UnhandledScript { module: module.to_owned(), function: function.to_owned(), recipes })
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::{craft::{SimCharacter, Simulator, SimulateError}, tests::node_book};
+
+    #[test]
+    fn registry() {
+        let book = node_book(&[
+            (1, "PID_ARMOR@@@@PID_HIDE 2@@PID_ARMOR 1@script fix_boy@fix_Tribal"),
+            (2, "PID_GLOVES@@@@PID_HIDE 1@@PID_GLOVES 1@script fix_boy@fix_FreeHands"),
+            (3, "PID_BOOTS@@@@PID_HIDE 1@@PID_BOOTS 1@script fix_boy@fix_FreeHands"),
+            (4, "PID_ROPE@@@@PID_HIDE 1@@PID_ROPE 1@exp 5"),
+        ]);
+        let mut registry = ScriptRegistry::new();
+        registry.bind("fix_boy", "fix_Tribal", |call: ScriptCall<'_>, state: &mut dyn CraftState<String>| {
+            assert_eq!("fix_boy@fix_Tribal", call.to_string());
+            state.add_experience(50);
+            Ok(())
+        });
+        assert_eq!(vec![UnhandledScript {
+            module: "fix_boy".to_owned(),
+            function: "fix_FreeHands".to_owned(),
+            recipes: vec![2, 3],
+        }], registry.unhandled_scripts(&book));
+
+        let mut state = SimCharacter::default();
+        state.items.insert("PID_HIDE".to_owned(), 5);
+        let mut simulator = Simulator::with_scripts(registry);
+        simulator.craft(&book[&1], &mut state).unwrap();
+        assert_eq!(50, state.experience);
+        let unhandled = SimulateError::UnhandledScript { module: "fix_boy".to_owned(), function: "fix_FreeHands".to_owned() };
+        assert_eq!(Err(unhandled), simulator.craft(&book[&2], &mut state));
+
+        simulator.scripts_mut().bind_default(|_: ScriptCall<'_>, _: &mut dyn CraftState<String>| Ok(()));
+        simulator.craft(&book[&2], &mut state).unwrap();
+        assert_eq!(Some(&1), state.items.get("PID_GLOVES"));
+        assert_eq!(1, simulator.scripts_mut().unhandled_scripts(&book).len());
+    }
+}
@@ -0,0 +1,154 @@
+use crate::{logic::{KeyValue, LogicChain, LogicNode, Logical}, recipe::{GenericRecipe, RecipeField}};
+
+use super::Requirement;
+
+/// Values of the keys an expression is checked against, absent keys count as 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment<K>(Vec<KeyValue<K>>);
+
+impl<K> Default for Assignment<K> {
+    fn default() -> Self {
+        Assignment(Vec::new())
+    }
+}
+
+impl<K: PartialEq> Assignment<K> {
+    pub fn get(&self, key: &K) -> u32 {
+        self.0.iter().find(|kv| kv.key == *key).map_or(0, |kv| kv.value)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &KeyValue<K>> {
+        self.0.iter()
+    }
+    /// Smallest assignment satisfying every key-value at once.
+    fn covering(kvs: Vec<KeyValue<K>>) -> Self {
+        let mut assignment = Assignment::default();
+        for kv in kvs {
+            match assignment.0.iter_mut().find(|known| known.key == kv.key) {
+                Some(known) => known.value = known.value.max(kv.value),
+                None => assignment.0.push(kv),
+            }
+        }
+        assignment
+    }
+}
+
+/// Assignment on which two expressions disagree.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Counterexample<K> {
+    /// Satisfies the left-hand expression only.
+    OnlyLeft(Assignment<K>),
+    /// Satisfies the right-hand expression only.
+    OnlyRight(Assignment<K>),
+}
+
+/// Comparison by meaning instead of structure, so `A 1 | B 1` equals `B 1 | A 1`.
+///
+/// Key-values are monotone `key >= value` atoms: raising any value never breaks a satisfied
+/// expression. Then `X` implies `Y` exactly when `Y` holds on every minimal assignment of `X`,
+/// one per term of its disjunctive normal form, which grows exponentially with nested ORs.
+pub trait Semantic: Requirement {
+    /// Minimal assignments satisfying the expression.
+    fn minimal_assignments(&self) -> Vec<Assignment<Self::Key>>;
+
+    fn holds(&self, assignment: &Assignment<Self::Key>) -> bool where Self::Key: PartialEq {
+        self.evaluate_with(&mut |kv| assignment.get(&kv.key) >= kv.value)
+    }
+    /// `Err` carries an assignment satisfying `self` but not `other`.
+    fn check_implies<O: Semantic<Key = Self::Key> + ?Sized>(&self, other: &O) -> Result<(), Assignment<Self::Key>> where Self::Key: PartialEq {
+        match self.minimal_assignments().into_iter().find(|assignment| !other.holds(assignment)) {
+            Some(assignment) => Err(assignment),
+            None => Ok(()),
+        }
+    }
+    fn implies<O: Semantic<Key = Self::Key> + ?Sized>(&self, other: &O) -> bool where Self::Key: PartialEq {
+        self.check_implies(other).is_ok()
+    }
+    fn check_equivalent<O: Semantic<Key = Self::Key> + ?Sized>(&self, other: &O) -> Result<(), Counterexample<Self::Key>> where Self::Key: PartialEq {
+        self.check_implies(other).map_err(Counterexample::OnlyLeft)?;
+        other.check_implies(self).map_err(Counterexample::OnlyRight)
+    }
+    fn equivalent<O: Semantic<Key = Self::Key> + ?Sized>(&self, other: &O) -> bool where Self::Key: PartialEq {
+        self.check_equivalent(other).is_ok()
+    }
+}
+
+impl<K: Clone + PartialEq> Semantic for LogicNode<K> {
+    fn minimal_assignments(&self) -> Vec<Assignment<K>> {
+        self.normal_groups(Logical::Or).into_iter().map(Assignment::covering).collect()
+    }
+}
+
+impl<K: Clone + PartialEq> Semantic for LogicChain<K> {
+    fn minimal_assignments(&self) -> Vec<Assignment<K>> {
+        self.clone().logic_nodes::<K>().minimal_assignments()
+    }
+}
+
+/// Missing optional block is no requirement at all, it holds on the empty assignment.
+fn check_block<K: PartialEq, A, B>(a: Option<&A>, b: Option<&B>) -> Result<(), Counterexample<K>>
+where
+    A: Semantic<Key = K>,
+    B: Semantic<Key = K>,
+{
+    match (a, b) {
+        (Some(a), Some(b)) => a.check_equivalent(b),
+        (None, None) => Ok(()),
+        (Some(a), None) if !a.holds(&Assignment::default()) => Err(Counterexample::OnlyRight(Assignment::default())),
+        (None, Some(b)) if !b.holds(&Assignment::default()) => Err(Counterexample::OnlyLeft(Assignment::default())),
+        _ => Ok(()),
+    }
+}
+
+impl<S, L: Semantic> GenericRecipe<S, L> where L::Key: PartialEq {
+    /// Compares every requirement block by meaning, ignoring name, description and side effect.
+    /// Use `with_keys` first to compare recipes with different key types.
+    pub fn check_equivalent_requirements<S2, L2: Semantic<Key = L::Key>>(
+        &self,
+        other: &GenericRecipe<S2, L2>,
+    ) -> Result<(), (RecipeField, Counterexample<L::Key>)> {
+        let blocks = [
+            (RecipeField::ParamsToSee, check_block(self.params_to_see.as_ref(), other.params_to_see.as_ref())),
+            (RecipeField::ParamsToCraft, check_block(self.params_to_craft.as_ref(), other.params_to_craft.as_ref())),
+            (RecipeField::Ingredients, self.ingredients.check_equivalent(&other.ingredients)),
+            (RecipeField::Tools, check_block(self.tools.as_ref(), other.tools.as_ref())),
+            (RecipeField::Output, self.output.check_equivalent(&other.output)),
+        ];
+        for (field, result) in blocks {
+            result.map_err(|counterexample| (field, counterexample))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    #[test]
+    fn reordered_recipes() {
+        let book = node_book(&[
+            (1, "PID_A@@@@PID_X 1|PID_Y 1&PID_Z 2@@PID_A 1@exp 1"),
+            (2, "PID_A@@@@PID_Z 2&(PID_Y 1|PID_X 1)@@PID_A 1@exp 1"),
+            (3, "PID_A@@@@PID_Z 2&PID_Y 1@@PID_A 1@exp 1"),
+        ]);
+        assert_eq!(Ok(()), book[&1].check_equivalent_requirements(&book[&2]));
+
+        let (field, counterexample) = book[&1].check_equivalent_requirements(&book[&3]).unwrap_err();
+        assert_eq!(RecipeField::Ingredients, field);
+        let Counterexample::OnlyLeft(assignment) = counterexample else { panic!("{counterexample:?}") };
+        assert_eq!((1, 0, 2), (assignment.get(&"PID_X".into()), assignment.get(&"PID_Y".into()), assignment.get(&"PID_Z".into())));
+
+        assert!(book[&3].ingredients.implies(&book[&1].ingredients));
+        assert!(!book[&1].ingredients.implies(&book[&3].ingredients));
+    }
+
+    #[test]
+    fn thresholds() {
+        let book = node_book(&[
+            (1, "PID_A@@@@PID_X 3|PID_X 1&PID_X 2@@PID_A 1@exp 1"),
+            (2, "PID_A@@@@PID_X 2@@PID_A 1@exp 1"),
+        ]);
+        assert!(book[&1].ingredients.equivalent(&book[&2].ingredients));
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+
+use crate::{key::KeyMeaning, logic::{KeyValue, LogicVisit}, recipe::{GenericRecipe, RecipeField, SideEffect}};
+
+use super::{Character, Consumable, ConsumeError, ConsumptionStrategy, FirstSatisfiable, ScriptCall, ScriptRegistry};
+
+/// Character whose inventory and experience a simulated craft can change.
+/// Every mutator returns the amount actually applied, which is what a rollback undoes.
+pub trait CraftState<K>: Character<K> {
+    fn add_item(&mut self, key: &K, count: u32) -> u32;
+    /// At most what the character has.
+    fn remove_item(&mut self, key: &K, count: u32) -> u32;
+    fn add_experience(&mut self, exp: u32) -> u32;
+    fn remove_experience(&mut self, exp: u32) -> u32;
+}
+
+/// Plain in-memory character for simulations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimCharacter<K: Ord> {
+    pub params: BTreeMap<K, u32>,
+    pub items: BTreeMap<K, u32>,
+    pub experience: u32,
+}
+
+impl<K: Ord> Default for SimCharacter<K> {
+    fn default() -> Self {
+        SimCharacter { params: BTreeMap::new(), items: BTreeMap::new(), experience: 0 }
+    }
+}
+
+impl<K: Ord> Character<K> for SimCharacter<K> {
+    fn value(&self, key: &K, meaning: KeyMeaning) -> u32 {
+        let values = match meaning {
+            KeyMeaning::Param => &self.params,
+            KeyMeaning::Item => &self.items,
+        };
+        values.get(key).copied().unwrap_or(0)
+    }
+}
+
+impl<K: Ord + Clone> CraftState<K> for SimCharacter<K> {
+    fn add_item(&mut self, key: &K, count: u32) -> u32 {
+        if count == 0 {
+            return 0;
+        }
+        let have = self.items.entry(key.clone()).or_insert(0);
+        let added = count.min(u32::MAX - *have);
+        *have += added;
+        added
+    }
+    fn remove_item(&mut self, key: &K, count: u32) -> u32 {
+        let Some(have) = self.items.get_mut(key) else { return 0 };
+        let removed = count.min(*have);
+        *have -= removed;
+        if *have == 0 {
+            self.items.remove(key);
+        }
+        removed
+    }
+    fn add_experience(&mut self, exp: u32) -> u32 {
+        let added = exp.min(u32::MAX - self.experience);
+        self.experience += added;
+        added
+    }
+    fn remove_experience(&mut self, exp: u32) -> u32 {
+        let removed = exp.min(self.experience);
+        self.experience -= removed;
+        removed
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<K> {
+    Added(KeyValue<K>),
+    Removed(KeyValue<K>),
+    ExperienceAdded(u32),
+    ExperienceRemoved(u32),
+}
+
+/// Changes made by one simulated craft, scripts included.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction<K> {
+    changes: Vec<Change<K>>,
+}
+
+impl<K> Transaction<K> {
+    pub fn changes(&self) -> &[Change<K>] {
+        &self.changes
+    }
+    /// Undoes the changes, newest first. `state` must be the one the craft was applied to.
+    pub fn rollback(self, state: &mut (impl CraftState<K> + ?Sized)) {
+        for change in self.changes.into_iter().rev() {
+            match change {
+                Change::Added(kv) => {
+                    state.remove_item(&kv.key, kv.value);
+                }
+                Change::Removed(kv) => {
+                    state.add_item(&kv.key, kv.value);
+                }
+                Change::ExperienceAdded(exp) => {
+                    state.remove_experience(exp);
+                }
+                Change::ExperienceRemoved(exp) => {
+                    state.add_experience(exp);
+                }
+            }
+        }
+    }
+}
+
+/// Wraps the simulated state, logging every change so scripts can be rolled back too.
+struct Recorder<'s, St: ?Sized, K> {
+    state: &'s mut St,
+    changes: Vec<Change<K>>,
+}
+
+impl<St: CraftState<K> + ?Sized, K> Character<K> for Recorder<'_, St, K> {
+    fn value(&self, key: &K, meaning: KeyMeaning) -> u32 {
+        self.state.value(key, meaning)
+    }
+}
+
+impl<St: CraftState<K> + ?Sized, K: Clone> CraftState<K> for Recorder<'_, St, K> {
+    fn add_item(&mut self, key: &K, count: u32) -> u32 {
+        let added = self.state.add_item(key, count);
+        if added > 0 {
+            self.changes.push(Change::Added(KeyValue::new(key.clone(), added)));
+        }
+        added
+    }
+    fn remove_item(&mut self, key: &K, count: u32) -> u32 {
+        let removed = self.state.remove_item(key, count);
+        if removed > 0 {
+            self.changes.push(Change::Removed(KeyValue::new(key.clone(), removed)));
+        }
+        removed
+    }
+    fn add_experience(&mut self, exp: u32) -> u32 {
+        let added = self.state.add_experience(exp);
+        if added > 0 {
+            self.changes.push(Change::ExperienceAdded(added));
+        }
+        added
+    }
+    fn remove_experience(&mut self, exp: u32) -> u32 {
+        let removed = self.state.remove_experience(exp);
+        if removed > 0 {
+            self.changes.push(Change::ExperienceRemoved(removed));
+        }
+        removed
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulateError {
+    /// `params_to_see` or `params_to_craft` isn't satisfied.
+    Params(RecipeField),
+    Consume(ConsumeError),
+    /// No handler bound for the recipe's script, and no default one.
+    UnhandledScript { module: String, function: String },
+    /// Script handler failed, every change of the craft was rolled back.
+    Script(String),
+}
+
+impl std::fmt::Display for SimulateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SimulateError::Params(field) => write!(f, "{field} isn't satisfied"),
+            SimulateError::Consume(err) => write!(f, "{err}"),
+            SimulateError::UnhandledScript { module, function } => write!(f, "no handler for script {module}@{function}"),
+            SimulateError::Script(err) => write!(f, "script failed: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SimulateError {}
+
+impl From<ConsumeError> for SimulateError {
+    fn from(err: ConsumeError) -> Self {
+        SimulateError::Consume(err)
+    }
+}
+
+/// Applies recipes to a [`CraftState`] the way the server would.
+pub struct Simulator<'c, K> {
+    scripts: ScriptRegistry<'c, K>,
+}
+
+impl<K> Default for Simulator<'_, K> {
+    fn default() -> Self {
+        Simulator { scripts: ScriptRegistry::new() }
+    }
+}
+
+impl<'c, K: Clone + PartialEq> Simulator<'c, K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn with_scripts(scripts: ScriptRegistry<'c, K>) -> Self {
+        Simulator { scripts }
+    }
+    pub fn scripts_mut(&mut self) -> &mut ScriptRegistry<'c, K> {
+        &mut self.scripts
+    }
+    /// Shortcut for binding a handler in the script registry.
+    pub fn on_script(&mut self, module: &str, function: &str, handler: impl FnMut(ScriptCall<'_>, &mut dyn CraftState<K>) -> Result<(), String> + 'c) -> &mut Self {
+        self.scripts.bind(module, function, handler);
+        self
+    }
+    /// Crafts once, taking the first ingredient alternative the inventory covers.
+    pub fn craft<S: AsRef<str>, L: LogicVisit<Key = K> + Consumable<Key = K>>(
+        &mut self,
+        recipe: &GenericRecipe<S, L>,
+        state: &mut impl CraftState<K>,
+    ) -> Result<Transaction<K>, SimulateError> {
+        self.craft_with(recipe, state, &FirstSatisfiable)
+    }
+    /// Either applies every change of the craft or none.
+    pub fn craft_with<S: AsRef<str>, L: LogicVisit<Key = K> + Consumable<Key = K>>(
+        &mut self,
+        recipe: &GenericRecipe<S, L>,
+        state: &mut impl CraftState<K>,
+        strategy: &impl ConsumptionStrategy<K>,
+    ) -> Result<Transaction<K>, SimulateError> {
+        let params = [(RecipeField::ParamsToSee, &recipe.params_to_see), (RecipeField::ParamsToCraft, &recipe.params_to_craft)];
+        for (field, params) in params {
+            if !params.as_ref().map_or(true, |params| params.is_satisfied(state, KeyMeaning::Param)) {
+                return Err(SimulateError::Params(field));
+            }
+        }
+        let consumed = recipe.plan_consumption(state, strategy)?;
+        let call = recipe.side_effect.script_call();
+        let mut handler = None;
+        if let Some(call) = call {
+            handler = self.scripts.handler_mut(call);
+            if handler.is_none() {
+                return Err(SimulateError::UnhandledScript { module: call.module.to_owned(), function: call.function.to_owned() });
+            }
+        }
+
+        let mut recorder = Recorder { state, changes: Vec::new() };
+        for kv in &consumed {
+            recorder.remove_item(&kv.key, kv.value);
+        }
+        recipe.output.visit(&mut |kv| {
+            recorder.add_item(&kv.key, kv.value);
+        });
+        if let SideEffect::Experience(exp) = recipe.side_effect {
+            recorder.add_experience(exp);
+        }
+        if let (Some(call), Some(handler)) = (call, handler) {
+            if let Err(err) = handler.run(call, &mut recorder) {
+                let Recorder { state, changes } = recorder;
+                Transaction { changes }.rollback(state);
+                return Err(SimulateError::Script(err));
+            }
+        }
+        Ok(Transaction { changes: recorder.changes })
+    }
+}
+
+#[cfg(all(test, feature = "parse"))]
+mod tests {
+    use super::*;
+    use crate::tests::node_book;
+
+    fn character() -> SimCharacter<String> {
+        let mut character = SimCharacter::default();
+        character.params.insert("SK_OUTDOORSMAN".to_owned(), 50);
+        for (item, count) in [("PID_MEAT", 5), ("PID_FIRE", 1)] {
+            character.items.insert(item.to_owned(), count);
+        }
+        character
+    }
+
+    #[test]
+    fn craft_and_rollback() {
+        let book = node_book(&[
+            (1, "PID_JERKY@@@SK_OUTDOORSMAN 20@PID_MEAT 4@PID_FIRE 1@PID_JERKY 3&PID_BONE 1@exp 10"),
+            (2, "PID_JERKY@@@SK_OUTDOORSMAN 80@PID_MEAT 4@PID_FIRE 1@PID_JERKY 3@exp 10"),
+        ]);
+        let mut state = character();
+        let mut simulator = Simulator::new();
+        let transaction = simulator.craft(&book[&1], &mut state).unwrap();
+        assert_eq!(Some(&3), state.items.get("PID_JERKY"));
+        assert_eq!(Some(&1), state.items.get("PID_MEAT"));
+        assert_eq!(Some(&1), state.items.get("PID_FIRE"));
+        assert_eq!(10, state.experience);
+        assert_eq!(4, transaction.changes().len());
+
+        assert_eq!(Err(SimulateError::Consume(ConsumeError::NoAssignment)), simulator.craft(&book[&1], &mut state));
+        assert_eq!(Err(SimulateError::Params(RecipeField::ParamsToCraft)), simulator.craft(&book[&2], &mut state));
+        transaction.rollback(&mut state);
+        assert_eq!(character(), state);
+    }
+
+    #[test]
+    fn scripts() {
+        let book = node_book(&[
+            (1, "PID_JERKY@@@@PID_MEAT 4@@PID_JERKY 3@script craft@bonus"),
+            (2, "PID_JERKY@@@@PID_MEAT 1@@PID_JERKY 1@script craft@fail"),
+            (3, "PID_JERKY@@@@PID_MEAT 1@@PID_JERKY 1@script craft@unknown"),
+        ]);
+        let mut calls = 0;
+        let mut simulator = Simulator::new();
+        simulator
+            .on_script("craft", "bonus", |_, state| {
+                calls += 1;
+                state.add_item(&"PID_BONE".to_owned(), 2);
+                Ok(())
+            })
+            .on_script("craft", "fail", |_, state| {
+                state.remove_item(&"PID_FIRE".to_owned(), 1);
+                Err("no luck".to_owned())
+            });
+        let mut state = character();
+        let transaction = simulator.craft(&book[&1], &mut state).unwrap();
+        assert_eq!(Some(&2), state.items.get("PID_BONE"));
+
+        let before = state.clone();
+        assert_eq!(Err(SimulateError::Script("no luck".to_owned())), simulator.craft(&book[&2], &mut state));
+        assert_eq!(before, state);
+        let unhandled = SimulateError::UnhandledScript { module: "craft".to_owned(), function: "unknown".to_owned() };
+        assert_eq!(Err(unhandled), simulator.craft(&book[&3], &mut state));
+
+        transaction.rollback(&mut state);
+        drop(simulator);
+        assert_eq!((character(), 1), (state, calls));
+    }
+
+    #[test]
+    fn failed_script_restores_experience() {
+        let book = node_book(&[(1, "PID_JERKY@@@@PID_MEAT 1@@PID_JERKY 1@script craft@penalty")]);
+        let mut simulator = Simulator::new();
+        simulator.on_script("craft", "penalty", |_, state| {
+            state.remove_experience(30);
+            Err("burnt".to_owned())
+        });
+        let mut state = character();
+        state.experience = 20;
+        let before = state.clone();
+        assert_eq!(Err(SimulateError::Script("burnt".to_owned())), simulator.craft(&book[&1], &mut state));
+        assert_eq!(before, state);
+    }
+
+    #[test]
+    fn rollback_undoes_applied_amount() {
+        let book = node_book(&[(1, "PID_JERKY@@@@PID_MEAT 1@@PID_JERKY 3@exp 10")]);
+        let mut state = character();
+        state.items.insert("PID_JERKY".to_owned(), u32::MAX - 1);
+        state.experience = u32::MAX - 4;
+        let before = state.clone();
+        let transaction = Simulator::new().craft(&book[&1], &mut state).unwrap();
+        assert!(transaction.changes().contains(&Change::Added(KeyValue::new("PID_JERKY".to_owned(), 1))));
+        assert!(transaction.changes().contains(&Change::ExperienceAdded(4)));
+        transaction.rollback(&mut state);
+        assert_eq!(before, state);
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::{Display, Write};
+
+use crate::recipe::RecipeField;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeFormat {
+    Textual,
+    /// Textual with bracketed requirements.
+    Bracketed,
+    Numeric,
+}
+
+impl Display for RecipeFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RecipeFormat::Textual => "textual",
+            RecipeFormat::Bracketed => "bracketed textual",
+            RecipeFormat::Numeric => "numeric",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecipeError {
+    /// Recipe line doesn't match the grammar.
+    Syntax(SyntaxError),
+    /// Recipe parsed fine, but in the other format than the caller asked for.
+    UnexpectedFormat { index: Option<u32>, expected: RecipeFormat, found: RecipeFormat },
+}
+
+impl RecipeError {
+    /// MSG index of the failed recipe, if it is known.
+    pub fn index(&self) -> Option<u32> {
+        match self {
+            RecipeError::Syntax(err) => err.index,
+            RecipeError::UnexpectedFormat { index, .. } => *index,
+        }
+    }
+    pub(crate) fn with_index(mut self, new_index: u32) -> Self {
+        match &mut self {
+            RecipeError::Syntax(err) => err.index = Some(new_index),
+            RecipeError::UnexpectedFormat { index, .. } => *index = Some(new_index),
+        }
+        self
+    }
+    /// Multi-line report with the failed line and carets under the failing tokens.
+    pub fn render(&self) -> String {
+        match self {
+            RecipeError::Syntax(err) => err.render(),
+            RecipeError::UnexpectedFormat { .. } => self.to_string(),
+        }
+    }
+}
+
+impl Display for RecipeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecipeError::Syntax(err) => err.fmt(f),
+            RecipeError::UnexpectedFormat { index, expected, found } => {
+                write_index(f, *index)?;
+                write!(f, "expected {expected} recipe, found {found} one")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecipeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePosition {
+    /// Byte offset from the start of the recipe line.
+    pub offset: usize,
+    /// 1-based.
+    pub line: usize,
+    /// 1-based, in chars.
+    pub column: usize,
+}
+
+impl SourcePosition {
+    pub(crate) fn new(source: &str, offset: usize) -> Self {
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |pos| pos + 1);
+        SourcePosition {
+            offset,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorFrameKind {
+    Expected(String),
+    Context(&'static str),
+}
+
+/// One entry of the parser error stack, innermost first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorFrame {
+    pub position: SourcePosition,
+    pub kind: ErrorFrameKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub index: Option<u32>,
+    /// Innermost recipe field the parser was in.
+    pub field: Option<RecipeField>,
+    pub position: SourcePosition,
+    pub expected: String,
+    pub source: String,
+    pub frames: Vec<ErrorFrame>,
+}
+
+impl SyntaxError {
+    pub fn render(&self) -> String {
+        let mut out = self.to_string();
+        for frame in &self.frames {
+            let line = self.source.lines().nth(frame.position.line - 1).unwrap_or("");
+            let label = match &frame.kind {
+                ErrorFrameKind::Expected(expected) => format!("expected {expected}"),
+                ErrorFrameKind::Context(context) => format!("in {context}"),
+            };
+            let _ = write!(out, "\n{:>4} | {line}\n     | {:>width$} {label}", frame.position.line, '^', width = frame.position.column);
+        }
+        out
+    }
+}
+
+impl Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_index(f, self.index)?;
+        if let Some(field) = self.field {
+            write!(f, "invalid {field} ")?;
+        } else {
+            write!(f, "invalid recipe ")?;
+        }
+        write!(f, "at line {}, column {}: expected {}", self.position.line, self.position.column, self.expected)
+    }
+}
+
+fn write_index(f: &mut std::fmt::Formatter<'_>, index: Option<u32>) -> std::fmt::Result {
+    match index {
+        Some(index) => write!(f, "recipe #{index}: "),
+        None => Ok(()),
+    }
+}
@@ -18,6 +18,15 @@ impl<'a> Key<'a> for String {
     }
 }
 
+/// Mirrors what the lexer accepts as a key or a script function name.
+pub(crate) fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+pub(crate) fn is_word(str: &str) -> bool {
+    !str.is_empty() && str.chars().all(is_word_char)
+}
+
 #[derive(Clone, Copy)]
 pub enum KeyMeaning {
     Param,
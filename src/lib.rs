@@ -1,14 +1,24 @@
+// `map_or(true, ..)` stands in for `Option::is_none_or`, which would need Rust 1.82
+#![allow(clippy::unnecessary_map_or)]
+
 #[cfg(feature = "display")]
 pub mod display;
 #[cfg(feature = "parse")]
 mod parse;
+#[cfg(feature = "serialize")]
+pub mod serialize;
+#[cfg(feature = "msg")]
+pub mod msg;
 pub mod logic;
 pub mod book;
 pub mod recipe;
 mod key;
 pub mod typed;
+pub mod error;
+pub mod craft;
 
-type RecipeError = String;
+pub use error::RecipeError;
+pub use key::KeyMeaning;
 
 pub type Recipe<S, K> = recipe::GenericRecipe<S, logic::LogicChain<K>>;
 pub type NodeRecipe<S, K> = recipe::GenericRecipe<S, logic::LogicNode<K>>;
@@ -21,6 +31,25 @@ pub type OwnedRecipeBook = book::RecipeBook<OwnedNodeRecipe>;
 mod tests {
     use crate::{UserFriendlyRecipeBook, book::RecipeBook, Recipe, key::KeyMeaning, NodeRecipe};
 
+    #[cfg(feature = "parse")]
+    pub(crate) fn node_book(lines: &[(u32, &str)]) -> UserFriendlyRecipeBook {
+        RecipeBook::try_from_iter(lines.iter().copied()).unwrap()
+    }
+
+    /// Character with the given params and items, 0 for anything else.
+    pub(crate) fn character(params: &[(&str, u32)], items: &[(&str, u32)]) -> impl Fn(&String, KeyMeaning) -> u32 {
+        let collect = |values: &[(&str, u32)]| values.iter().map(|(key, value)| (key.to_string(), *value)).collect::<std::collections::BTreeMap<_, _>>();
+        let (params, items) = (collect(params), collect(items));
+        move |key, meaning| match meaning {
+            KeyMeaning::Param => params.get(key).copied().unwrap_or(0),
+            KeyMeaning::Item => items.get(key).copied().unwrap_or(0),
+        }
+    }
+
+    pub(crate) fn pairs(kvs: &[crate::logic::KeyValue<String>]) -> Vec<(&str, u32)> {
+        kvs.iter().map(|kv| (kv.key.as_str(), kv.value)).collect()
+    }
+
     fn _readable_local_recipes<'a, I: Iterator<Item = (u32, &'a str)>>(lines: I) -> Result<UserFriendlyRecipeBook, String> {
         let lst = fo_lst_format::parse_dir("../../FO4RP/data").map_err(|err| format!("Can't parse LST files: {err}"))?;
         let book = RecipeBook::<Recipe<&str, u32>>::try_from_iter(lines).map_err(|err| format!("Can't parse craft book: {err}"))?;
@@ -1,6 +1,8 @@
-use super::{LogicType, KeyValue, Logical, LogicNode};
+use std::fmt::Display;
 
-#[derive(PartialEq, Debug)]
+use super::{LogicType, LogicVisit, LogicFolder, KeyValue, Logical, LogicNode};
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct LogicChain<K> {
     pub(crate) first: KeyValue<K>,
     pub(crate) rest: Vec<(Logical, KeyValue<K>)>,
@@ -19,6 +21,51 @@ impl<K> LogicType for LogicChain<K> {
     }
 }
 
+impl<K> LogicVisit for LogicChain<K> {
+    fn visit<'a>(&'a self, f: &mut dyn FnMut(&'a KeyValue<K>)) {
+        f(&self.first);
+        self.rest.iter().for_each(|(_, kv)| f(kv));
+    }
+
+    fn visit_mut(&mut self, f: &mut dyn FnMut(&mut KeyValue<K>)) {
+        f(&mut self.first);
+        self.rest.iter_mut().for_each(|(_, kv)| f(kv));
+    }
+
+    fn fold<F: LogicFolder<K>>(&self, folder: &mut F) -> F::Output {
+        fn flush<K, F: LogicFolder<K>>(folder: &mut F, mut or_section: Vec<F::Output>) -> F::Output {
+            if or_section.len() == 1 {
+                or_section.remove(0)
+            } else {
+                folder.group(Logical::Or, or_section)
+            }
+        }
+        let mut or_section = vec![folder.key_value(&self.first)];
+        let mut and_section = Vec::new();
+        for (logical, kv) in &self.rest {
+            if *logical == Logical::And {
+                let section = std::mem::take(&mut or_section);
+                and_section.push(flush(folder, section));
+            }
+            or_section.push(folder.key_value(kv));
+        }
+        let last = flush(folder, or_section);
+        if and_section.is_empty() {
+            last
+        } else {
+            and_section.push(last);
+            folder.group(Logical::And, and_section)
+        }
+    }
+}
+
+impl<K> LogicChain<K> {
+    /// `rest` pairs each key-value with the operator joining it to the previous one.
+    pub fn new(first: KeyValue<K>, rest: Vec<(Logical, KeyValue<K>)>) -> Self {
+        LogicChain { first, rest }
+    }
+}
+
 impl<K: Clone> LogicChain<K> {
     pub fn logic_nodes<K2: From<K>>(self) -> LogicNode<K2> {
         if self.rest.is_empty() {
@@ -57,3 +104,142 @@ impl<K: Clone> LogicChain<K> {
         }
     }
 }
+
+/// Why a `LogicNode` can't be lowered into the flat AND-of-ORs chain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LowerError<K> {
+    /// `And` or `Or` without children.
+    EmptyGroup,
+    /// AND nested inside OR, the offending sub-expression is attached.
+    AndInsideOr(LogicNode<K>),
+}
+
+impl<K: Display> Display for LowerError<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LowerError::EmptyGroup => write!(f, "empty AND/OR group"),
+            LowerError::AndInsideOr(_node) => {
+                #[cfg(feature = "display")]
+                {
+                    use crate::display::{LogicDisplay, LogicFmtConfig};
+                    let config = LogicFmtConfig::new(" & ".to_owned(), " | ".to_owned()).value_prefix(" ".to_owned());
+                    write!(f, "`{}` is ", _node.format(&config))?;
+                }
+                write!(f, "an AND inside an OR, which a flat chain can't express")
+            }
+        }
+    }
+}
+
+impl<K: Display + std::fmt::Debug> std::error::Error for LowerError<K> {}
+
+/// Inverse of [`LogicChain::logic_nodes`]. Nested groups of the same operator and single-child groups
+/// are flattened; ANDs are never distributed over ORs, since that would change which ingredients
+/// are consumed.
+impl<K> TryFrom<LogicNode<K>> for LogicChain<K> {
+    type Error = LowerError<K>;
+
+    fn try_from(node: LogicNode<K>) -> Result<Self, Self::Error> {
+        let mut and_section = Vec::new();
+        collect_and_section(node, &mut and_section)?;
+        let mut kvs = and_section.into_iter().flat_map(|or_section| {
+            or_section.into_iter().enumerate().map(|(i, kv)| (if i == 0 { Logical::And } else { Logical::Or }, kv))
+        });
+        let (_, first) = kvs.next().ok_or(LowerError::EmptyGroup)?;
+        Ok(LogicChain { first, rest: kvs.collect() })
+    }
+}
+
+fn collect_and_section<K>(node: LogicNode<K>, and_section: &mut Vec<Vec<KeyValue<K>>>) -> Result<(), LowerError<K>> {
+    match node {
+        LogicNode::KeyValue(kv) => and_section.push(vec![kv]),
+        LogicNode::And(nodes) => {
+            if nodes.is_empty() {
+                return Err(LowerError::EmptyGroup);
+            }
+            for node in nodes {
+                collect_and_section(node, and_section)?;
+            }
+        }
+        // `Or` around a single `And` is still flat
+        LogicNode::Or(mut nodes) if nodes.len() == 1 => collect_and_section(nodes.remove(0), and_section)?,
+        LogicNode::Or(nodes) => {
+            let mut or_section = Vec::new();
+            collect_or_section(LogicNode::Or(nodes), &mut or_section)?;
+            and_section.push(or_section);
+        }
+    }
+    Ok(())
+}
+
+fn collect_or_section<K>(node: LogicNode<K>, or_section: &mut Vec<KeyValue<K>>) -> Result<(), LowerError<K>> {
+    match node {
+        LogicNode::KeyValue(kv) => or_section.push(kv),
+        LogicNode::Or(nodes) | LogicNode::And(nodes) if nodes.is_empty() => return Err(LowerError::EmptyGroup),
+        LogicNode::Or(nodes) => {
+            for node in nodes {
+                collect_or_section(node, or_section)?;
+            }
+        }
+        LogicNode::And(mut nodes) if nodes.len() == 1 => collect_or_section(nodes.remove(0), or_section)?,
+        node @ LogicNode::And(_) => return Err(LowerError::AndInsideOr(node)),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kv(key: &str) -> LogicNode<&str> {
+        LogicNode::KeyValue(KeyValue { key, value: 1 })
+    }
+
+    #[test]
+    fn lower_roundtrip() {
+        let chain = LogicChain {
+            first: KeyValue { key: "A", value: 1 },
+            rest: vec![
+                (Logical::Or, KeyValue { key: "B", value: 1 }),
+                (Logical::And, KeyValue { key: "C", value: 1 }),
+                (Logical::And, KeyValue { key: "D", value: 1 }),
+                (Logical::Or, KeyValue { key: "E", value: 1 }),
+            ],
+        };
+        let nodes: LogicNode<&str> = chain.clone().logic_nodes();
+        assert_eq!(Ok(chain), LogicChain::try_from(nodes));
+    }
+
+    #[test]
+    fn lower_normalizes_nesting() {
+        let nested = LogicNode::And(vec![
+            LogicNode::And(vec![kv("A"), LogicNode::Or(vec![kv("B")])]),
+            LogicNode::Or(vec![LogicNode::Or(vec![kv("C"), kv("D")]), LogicNode::And(vec![kv("E")])]),
+        ]);
+        let flat = LogicChain::try_from(LogicNode::And(vec![
+            kv("A"),
+            kv("B"),
+            LogicNode::Or(vec![kv("C"), kv("D"), kv("E")]),
+        ]));
+        assert_eq!(flat, LogicChain::try_from(nested));
+    }
+
+    #[test]
+    fn lower_single_child_or() {
+        let wrapped = LogicNode::Or(vec![LogicNode::And(vec![kv("A"), LogicNode::Or(vec![kv("B"), kv("C")])])]);
+        let flat = LogicChain::try_from(LogicNode::And(vec![kv("A"), LogicNode::Or(vec![kv("B"), kv("C")])]));
+        assert!(flat.is_ok());
+        assert_eq!(flat, LogicChain::try_from(wrapped));
+        assert_eq!(flat, LogicChain::try_from(LogicNode::And(vec![LogicNode::Or(vec![LogicNode::Or(vec![kv("A")])]), LogicNode::Or(vec![kv("B"), kv("C")])])));
+    }
+
+    #[test]
+    fn lower_errors() {
+        let and = LogicNode::And(vec![kv("B"), kv("C")]);
+        let err = LogicChain::try_from(LogicNode::And(vec![kv("Z"), LogicNode::Or(vec![kv("A"), and.clone()])])).unwrap_err();
+        assert_eq!(LowerError::AndInsideOr(and), err);
+        #[cfg(feature = "display")]
+        assert_eq!("`B 1 & C 1` is an AND inside an OR, which a flat chain can't express", err.to_string());
+        assert_eq!(Err(LowerError::EmptyGroup), LogicChain::try_from(LogicNode::Or(vec![kv("A"), LogicNode::And(vec![])])));
+    }
+}
@@ -1,9 +1,12 @@
 mod node;
 mod chain;
+mod normalize;
+mod visit;
 
 pub use self::{
     node::LogicNode,
-    chain::LogicChain,
+    chain::{LogicChain, LowerError},
+    visit::{KeyValues, Keys, LogicFolder, LogicVisit},
 };
 
 pub trait LogicType: Sized {
@@ -29,6 +32,21 @@ impl<K: Clone> KeyValue<K> {
     }
 }
 impl<K> KeyValue<K> {
+    pub fn new(key: K, value: u32) -> Self {
+        KeyValue { key, value }
+    }
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+    pub fn value(&self) -> u32 {
+        self.value
+    }
+    pub fn set_key(&mut self, key: K) {
+        self.key = key;
+    }
+    pub fn set_value(&mut self, value: u32) {
+        self.value = value;
+    }
     fn convert_with<K2, E, F: Fn(&K)->Result<K2, E>>(&self, f: F) -> Result<KeyValue<K2>, E> {
         Ok(KeyValue { key: f(&self.key)?, value: self.value })
     }
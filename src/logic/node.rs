@@ -1,6 +1,6 @@
-use super::{KeyValue, LogicType};
+use super::{KeyValue, LogicFolder, LogicType, LogicVisit, Logical};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum LogicNode<K> {
     And(Vec<LogicNode<K>>),
     Or(Vec<LogicNode<K>>),
@@ -20,3 +20,39 @@ impl<K> LogicType for LogicNode<K> {
         })
     }
 }
+
+impl<K> LogicVisit for LogicNode<K> {
+    fn visit<'a>(&'a self, f: &mut dyn FnMut(&'a KeyValue<K>)) {
+        match self {
+            LogicNode::And(nodes) | LogicNode::Or(nodes) => nodes.iter().for_each(|node| node.visit(f)),
+            LogicNode::KeyValue(kv) => f(kv),
+        }
+    }
+
+    fn visit_mut(&mut self, f: &mut dyn FnMut(&mut KeyValue<K>)) {
+        match self {
+            LogicNode::And(nodes) | LogicNode::Or(nodes) => nodes.iter_mut().for_each(|node| node.visit_mut(f)),
+            LogicNode::KeyValue(kv) => f(kv),
+        }
+    }
+
+    fn fold<F: LogicFolder<K>>(&self, folder: &mut F) -> F::Output {
+        let (logical, nodes) = match self {
+            LogicNode::And(nodes) => (Logical::And, nodes),
+            LogicNode::Or(nodes) => (Logical::Or, nodes),
+            LogicNode::KeyValue(kv) => return folder.key_value(kv),
+        };
+        let children = nodes.iter().map(|node| node.fold(folder)).collect();
+        folder.group(logical, children)
+    }
+}
+
+impl<K: Clone> LogicNode<K> {
+    pub(crate) fn convert<K2: From<K>>(self) -> LogicNode<K2> {
+        match self {
+            LogicNode::And(nodes) => LogicNode::And(nodes.into_iter().map(LogicNode::convert).collect()),
+            LogicNode::Or(nodes) => LogicNode::Or(nodes.into_iter().map(LogicNode::convert).collect()),
+            LogicNode::KeyValue(kv) => LogicNode::KeyValue(kv.convert()),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::cmp::Ordering;
+
+use super::{KeyValue, Logical, LogicNode};
+
+/// Normalization passes. `KeyValue` is read as a threshold `key >= value`, an empty `And` as
+/// true and an empty `Or` as false; every pass keeps the meaning under that reading, which is
+/// how params and tools are checked. Ingredients aren't thresholds: repeated keys are summed
+/// when consumed (see `Consumable`), so `PID_A 1&PID_A 3` takes 4 items while its deduped
+/// `PID_A 3` takes 3. Don't run `dedupe`, `absorb`, `simplify` or the normal forms on blocks
+/// whose consumption matters.
+impl<K: Clone + PartialEq> LogicNode<K> {
+    /// Unwraps single-child groups and splices nested groups of the same operator into their parent.
+    pub fn flatten(self) -> Self {
+        self.simplify_with(false, false)
+    }
+    /// Flattens and merges repeated keys of a group into one: an `And` keeps the max value.
+    /// An `Or` keeps the min rather than the max, since `A 1 | A 3` already holds with a single
+    /// `A` and the max would make the group stricter. Threshold blocks only, see above.
+    pub fn dedupe(self) -> Self {
+        self.simplify_with(true, false)
+    }
+    /// Flattens and drops children implied by a sibling, `A 2 & (A 1 | B 1)` becomes `A 2`.
+    pub fn absorb(self) -> Self {
+        self.simplify_with(false, true)
+    }
+    /// All of the above.
+    pub fn simplify(self) -> Self {
+        self.simplify_with(true, true)
+    }
+    /// Conjunctive normal form: AND of ORs of key-values, simplified.
+    pub fn to_cnf(&self) -> Self {
+        self.normal_form(Logical::And)
+    }
+    /// Disjunctive normal form: OR of ANDs of key-values, simplified.
+    pub fn to_dnf(&self) -> Self {
+        self.normal_form(Logical::Or)
+    }
+
+    fn simplify_with(self, dedupe: bool, absorb: bool) -> Self {
+        let (op, nodes) = match self {
+            LogicNode::KeyValue(_) => return self,
+            LogicNode::And(nodes) => (Logical::And, nodes),
+            LogicNode::Or(nodes) => (Logical::Or, nodes),
+        };
+        let mut children: Vec<LogicNode<K>> = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            match (op, node.simplify_with(dedupe, absorb)) {
+                (Logical::And, LogicNode::And(nested)) | (Logical::Or, LogicNode::Or(nested)) => children.extend(nested),
+                (_, node) => children.push(node),
+            }
+        }
+        if dedupe {
+            children = dedupe_children(op, children);
+        }
+        if absorb {
+            children = absorb_children(op, children);
+        }
+        if children.len() == 1 {
+            children.remove(0)
+        } else {
+            group(op, children)
+        }
+    }
+
+    fn normal_form(&self, outer: Logical) -> Self {
+        let inner = match outer {
+            Logical::And => Logical::Or,
+            Logical::Or => Logical::And,
+        };
+        let groups = self.normal_groups(outer)
+            .into_iter()
+            .map(|kvs| group(inner, kvs.into_iter().map(LogicNode::KeyValue).collect()))
+            .collect();
+        group(outer, groups).simplify()
+    }
+
+    /// Children of `outer` as lists of key-values joined by the other operator.
+    pub(crate) fn normal_groups(&self, outer: Logical) -> Vec<Vec<KeyValue<K>>> {
+        match (outer, self) {
+            (_, LogicNode::KeyValue(kv)) => vec![vec![kv.clone()]],
+            (Logical::And, LogicNode::And(nodes)) | (Logical::Or, LogicNode::Or(nodes)) => {
+                nodes.iter().flat_map(|node| node.normal_groups(outer)).collect()
+            }
+            (_, LogicNode::And(nodes)) | (_, LogicNode::Or(nodes)) => nodes.iter().fold(vec![vec![]], |acc, node| {
+                let groups = node.normal_groups(outer);
+                acc.iter()
+                    .flat_map(|prefix| groups.iter().map(move |group| prefix.iter().chain(group).cloned().collect()))
+                    .collect()
+            }),
+        }
+    }
+}
+
+impl<K: Clone + Ord> LogicNode<K> {
+    /// Simplified tree with every group's children sorted, so that trees differing only in
+    /// order compare equal. Combine with `to_cnf` or `to_dnf` for a fully canonical shape.
+    pub fn canonical(self) -> Self {
+        fn sort<K: Ord>(node: &mut LogicNode<K>) {
+            if let LogicNode::And(nodes) | LogicNode::Or(nodes) = node {
+                nodes.iter_mut().for_each(sort);
+                nodes.sort_by(compare);
+            }
+        }
+        let mut node = self.simplify();
+        sort(&mut node);
+        node
+    }
+}
+
+fn group<K>(op: Logical, nodes: Vec<LogicNode<K>>) -> LogicNode<K> {
+    match op {
+        Logical::And => LogicNode::And(nodes),
+        Logical::Or => LogicNode::Or(nodes),
+    }
+}
+
+fn dedupe_children<K: PartialEq>(op: Logical, nodes: Vec<LogicNode<K>>) -> Vec<LogicNode<K>> {
+    let mut children: Vec<LogicNode<K>> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        if let LogicNode::KeyValue(kv) = &node {
+            let known = children.iter_mut().find_map(|child| match child {
+                LogicNode::KeyValue(known) if known.key == kv.key => Some(known),
+                _ => None,
+            });
+            if let Some(known) = known {
+                known.value = match op {
+                    Logical::And => known.value.max(kv.value),
+                    Logical::Or => known.value.min(kv.value),
+                };
+                continue;
+            }
+        }
+        if !children.contains(&node) {
+            children.push(node);
+        }
+    }
+    children
+}
+
+fn absorb_children<K: PartialEq>(op: Logical, nodes: Vec<LogicNode<K>>) -> Vec<LogicNode<K>> {
+    // In an AND a child implied by a sibling adds nothing, in an OR a child implying a sibling does.
+    let redundant = |node: &LogicNode<K>, by: &LogicNode<K>| match op {
+        Logical::And => implies(by, node),
+        Logical::Or => implies(node, by),
+    };
+    let mut children: Vec<LogicNode<K>> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        if children.iter().any(|kept| redundant(&node, kept)) {
+            continue;
+        }
+        children.retain(|kept| !redundant(kept, &node));
+        children.push(node);
+    }
+    children
+}
+
+/// Syntactic implication, sound but not complete: `true` guarantees `a` implies `b`.
+pub(crate) fn implies<K: PartialEq>(a: &LogicNode<K>, b: &LogicNode<K>) -> bool {
+    match (a, b) {
+        (LogicNode::KeyValue(a), LogicNode::KeyValue(b)) => a.key == b.key && a.value >= b.value,
+        (_, LogicNode::And(bs)) => bs.iter().all(|b| implies(a, b)),
+        (LogicNode::Or(as_), _) => as_.iter().all(|a| implies(a, b)),
+        (LogicNode::And(as_), _) if as_.iter().any(|a| implies(a, b)) => true,
+        (_, LogicNode::Or(bs)) => bs.iter().any(|b| implies(a, b)),
+        _ => false,
+    }
+}
+
+fn compare<K: Ord>(a: &LogicNode<K>, b: &LogicNode<K>) -> Ordering {
+    match (a, b) {
+        (LogicNode::KeyValue(a), LogicNode::KeyValue(b)) => a.key.cmp(&b.key).then(a.value.cmp(&b.value)),
+        (LogicNode::KeyValue(_), _) => Ordering::Less,
+        (_, LogicNode::KeyValue(_)) => Ordering::Greater,
+        (LogicNode::And(a), LogicNode::And(b)) | (LogicNode::Or(a), LogicNode::Or(b)) => a
+            .iter()
+            .zip(b)
+            .map(|(a, b)| compare(a, b))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(a.len().cmp(&b.len())),
+        (LogicNode::And(_), LogicNode::Or(_)) => Ordering::Less,
+        (LogicNode::Or(_), LogicNode::And(_)) => Ordering::Greater,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kv(key: &'static str, value: u32) -> LogicNode<&'static str> {
+        LogicNode::KeyValue(KeyValue { key, value })
+    }
+    fn and(nodes: Vec<LogicNode<&'static str>>) -> LogicNode<&'static str> {
+        LogicNode::And(nodes)
+    }
+    fn or(nodes: Vec<LogicNode<&'static str>>) -> LogicNode<&'static str> {
+        LogicNode::Or(nodes)
+    }
+
+    #[test]
+    fn flatten_and_dedupe() {
+        let node = and(vec![and(vec![kv("A", 1), and(vec![kv("B", 1)])]), or(vec![kv("C", 1)]), kv("A", 3)]);
+        assert_eq!(and(vec![kv("A", 1), kv("B", 1), kv("C", 1), kv("A", 3)]), node.clone().flatten());
+        assert_eq!(and(vec![kv("A", 3), kv("B", 1), kv("C", 1)]), node.dedupe());
+        assert_eq!(kv("A", 1), or(vec![kv("A", 2), or(vec![kv("A", 1)])]).dedupe());
+    }
+
+    #[test]
+    fn absorption() {
+        assert_eq!(kv("A", 2), and(vec![kv("A", 2), or(vec![kv("A", 1), kv("B", 1)])]).absorb());
+        assert_eq!(kv("A", 1), or(vec![and(vec![kv("A", 1), kv("B", 1)]), kv("A", 1)]).absorb());
+        let kept = and(vec![kv("A", 1), or(vec![kv("A", 2), kv("B", 1)])]);
+        assert_eq!(kept, kept.clone().absorb());
+    }
+
+    #[test]
+    fn normal_forms() {
+        let node = or(vec![and(vec![kv("A", 1), kv("B", 1)]), kv("C", 1)]);
+        assert_eq!(and(vec![or(vec![kv("A", 1), kv("C", 1)]), or(vec![kv("B", 1), kv("C", 1)])]), node.to_cnf());
+        let node = and(vec![or(vec![kv("A", 1), kv("B", 1)]), or(vec![kv("A", 1), kv("C", 1)])]);
+        assert_eq!(or(vec![kv("A", 1), and(vec![kv("B", 1), kv("C", 1)])]), node.to_dnf());
+        assert_eq!(and(vec![]), and(vec![]).to_dnf());
+        assert_eq!(or(vec![]), or(vec![]).to_cnf());
+    }
+
+    #[test]
+    fn canonical_order() {
+        let a = and(vec![or(vec![kv("C", 1), kv("B", 1)]), kv("A", 1)]);
+        let b = and(vec![kv("A", 1), or(vec![kv("B", 1), kv("C", 1)])]);
+        assert_eq!(a.canonical(), b.canonical());
+    }
+}
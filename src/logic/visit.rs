@@ -0,0 +1,135 @@
+use super::{KeyValue, Logical, LogicType};
+
+/// Traversal of the key-values of a [`LogicType`]. A trait of its own, so existing `LogicType`
+/// implementors don't have to provide it.
+pub trait LogicVisit: LogicType {
+    /// Calls `f` for every key-value in expression order.
+    fn visit<'a>(&'a self, f: &mut dyn FnMut(&'a KeyValue<Self::Key>));
+    fn visit_mut(&mut self, f: &mut dyn FnMut(&mut KeyValue<Self::Key>));
+    /// A chain folds as the tree `LogicChain::logic_nodes` would build from it.
+    fn fold<F: LogicFolder<Self::Key>>(&self, folder: &mut F) -> F::Output;
+
+    fn key_values(&self) -> KeyValues<'_, Self::Key> {
+        let mut kvs = Vec::new();
+        self.visit(&mut |kv| kvs.push(kv));
+        KeyValues::new(kvs)
+    }
+    fn keys(&self) -> Keys<'_, Self::Key> {
+        Keys(self.key_values())
+    }
+    /// Value counterpart of `with_keys`, stops at the first error.
+    fn with_values<E, F: FnMut(&Self::Key, u32)->Result<u32, E>>(&self, mut f: F) -> Result<Self, E> where Self: Clone {
+        let mut logic = self.clone();
+        let mut error = None;
+        logic.visit_mut(&mut |kv| {
+            if error.is_none() {
+                match f(&kv.key, kv.value) {
+                    Ok(value) => kv.value = value,
+                    Err(err) => error = Some(err),
+                }
+            }
+        });
+        match error {
+            Some(err) => Err(err),
+            None => Ok(logic),
+        }
+    }
+}
+
+/// Key-values of a `LogicType` in expression order.
+pub struct KeyValues<'a, K>(std::vec::IntoIter<&'a KeyValue<K>>);
+
+impl<'a, K> KeyValues<'a, K> {
+    pub(crate) fn new(kvs: Vec<&'a KeyValue<K>>) -> Self {
+        KeyValues(kvs.into_iter())
+    }
+}
+
+impl<'a, K> Iterator for KeyValues<'a, K> {
+    type Item = &'a KeyValue<K>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K> ExactSizeIterator for KeyValues<'_, K> {}
+
+/// Keys of a `LogicType` in expression order, repeated keys included.
+pub struct Keys<'a, K>(pub(crate) KeyValues<'a, K>);
+
+impl<'a, K> Iterator for Keys<'a, K> {
+    type Item = &'a K;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(KeyValue::key)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K> ExactSizeIterator for Keys<'_, K> {}
+
+/// Bottom-up fold over the tree shape of an expression, see [`LogicVisit::fold`].
+pub trait LogicFolder<K> {
+    type Output;
+    fn key_value(&mut self, kv: &KeyValue<K>) -> Self::Output;
+    fn group(&mut self, logical: Logical, children: Vec<Self::Output>) -> Self::Output;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{LogicChain, LogicNode, LogicVisit};
+
+    fn kv(key: &str, value: u32) -> KeyValue<String> {
+        KeyValue { key: key.to_owned(), value }
+    }
+
+    fn chain() -> LogicChain<String> {
+        LogicChain {
+            first: kv("A", 1),
+            rest: vec![(Logical::Or, kv("B", 2)), (Logical::And, kv("C", 3))],
+        }
+    }
+
+    struct Render;
+
+    impl LogicFolder<String> for Render {
+        type Output = String;
+        fn key_value(&mut self, kv: &KeyValue<String>) -> String {
+            format!("{} {}", kv.key, kv.value)
+        }
+        fn group(&mut self, logical: Logical, children: Vec<String>) -> String {
+            let sep = match logical {
+                Logical::And => " & ",
+                Logical::Or => " | ",
+            };
+            format!("({})", children.join(sep))
+        }
+    }
+
+    #[test]
+    fn both_representations() {
+        let chain = chain();
+        let node: LogicNode<String> = chain.clone().logic_nodes();
+        assert_eq!(vec!["A", "B", "C"], chain.keys().collect::<Vec<_>>());
+        assert_eq!(chain.keys().collect::<Vec<_>>(), node.keys().collect::<Vec<_>>());
+        assert_eq!(6, node.key_values().map(KeyValue::value).sum::<u32>());
+        assert_eq!("((A 1 | B 2) & C 3)", chain.fold(&mut Render));
+        assert_eq!(chain.fold(&mut Render), node.fold(&mut Render));
+    }
+
+    #[test]
+    fn rewriting() {
+        let mut chain = chain();
+        chain.visit_mut(&mut |kv| if kv.key == "B" { kv.key = "D".to_owned() });
+        let doubled = chain.with_values(|_, value| value.checked_mul(2).ok_or(())).unwrap();
+        assert_eq!("((A 2 | D 4) & C 6)", doubled.fold(&mut Render));
+
+        let node: LogicNode<String> = chain.logic_nodes();
+        assert_eq!(Err("C"), node.with_values(|key, value| if key == "C" { Err("C") } else { Ok(value) }));
+    }
+}
@@ -0,0 +1,184 @@
+use std::{collections::BTreeSet, fmt::Display, io::Read, path::Path};
+
+use nom_prelude::{complete::*, *};
+
+use crate::{book::RecipeBook, recipe::AnyRecipe, RecipeError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MsgEncoding {
+    /// Windows-1251, the encoding of the original game files.
+    Cp1251,
+    Utf8,
+    /// UTF-8 if the file is valid UTF-8, Windows-1251 otherwise.
+    #[default]
+    Auto,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(std::io::Error),
+    InvalidUtf8,
+    /// Malformed `{index}{}{text}` entry starting at the given 1-based line.
+    Msg { line: usize },
+    Recipe(RecipeError),
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "can't read MSG file: {err}"),
+            LoadError::InvalidUtf8 => write!(f, "MSG file is not valid UTF-8"),
+            LoadError::Msg { line } => write!(f, "malformed MSG entry at line {line}"),
+            LoadError::Recipe(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<std::io::Error> for LoadError {
+    fn from(err: std::io::Error) -> Self {
+        LoadError::Io(err)
+    }
+}
+
+impl From<RecipeError> for LoadError {
+    fn from(err: RecipeError) -> Self {
+        LoadError::Recipe(err)
+    }
+}
+
+/// Decoded FOnline MSG file: `{index}{}{text}` entries, everything else on a line is a comment.
+pub struct MsgFile {
+    text: String,
+}
+
+impl MsgFile {
+    pub fn open(path: impl AsRef<Path>, encoding: MsgEncoding) -> Result<Self, LoadError> {
+        Self::from_bytes(std::fs::read(path)?, encoding)
+    }
+    pub fn from_reader(mut reader: impl Read, encoding: MsgEncoding) -> Result<Self, LoadError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(bytes, encoding)
+    }
+    pub fn from_bytes(bytes: Vec<u8>, encoding: MsgEncoding) -> Result<Self, LoadError> {
+        let utf8 = |bytes: Vec<u8>| String::from_utf8(bytes).map_err(|err| err.into_bytes());
+        let cp1251 = |bytes: Vec<u8>| encoding_rs::WINDOWS_1251.decode_without_bom_handling(&bytes).0.into_owned();
+        let mut text = match encoding {
+            MsgEncoding::Cp1251 => cp1251(bytes),
+            MsgEncoding::Utf8 => utf8(bytes).map_err(|_| LoadError::InvalidUtf8)?,
+            MsgEncoding::Auto => utf8(bytes).unwrap_or_else(cp1251),
+        };
+        if text.starts_with('\u{feff}') {
+            text.remove(0);
+        }
+        Ok(Self { text })
+    }
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+    /// Entries in file order, only the first one of every index is kept; the later ones are
+    /// listed by [`MsgFile::duplicates`].
+    pub fn entries(&self) -> Result<Vec<(u32, &str)>, LoadError> {
+        Ok(self.scan()?.0)
+    }
+    /// Indices of the entries `entries` drops for repeating an earlier index, in file order.
+    pub fn duplicates(&self) -> Result<Vec<u32>, LoadError> {
+        Ok(self.scan()?.1)
+    }
+    fn scan(&self) -> Result<Scan<'_>, LoadError> {
+        let mut entries = Vec::new();
+        let mut duplicates = Vec::new();
+        let mut seen = BTreeSet::new();
+        let mut rest = self.text.as_str();
+        loop {
+            rest = rest.trim_start();
+            if rest.is_empty() {
+                break;
+            }
+            if rest.starts_with('{') {
+                let (next, (index, text)) = msg_entry::<(&str, ErrorKind)>(rest).map_err(|_| {
+                    LoadError::Msg { line: self.text[..self.text.len() - rest.len()].matches('\n').count() + 1 }
+                })?;
+                if seen.insert(index) {
+                    entries.push((index, text));
+                } else {
+                    duplicates.push(index);
+                }
+                rest = next;
+            } else {
+                rest = rest.find('\n').map_or("", |pos| &rest[pos..]);
+            }
+        }
+        Ok((entries, duplicates))
+    }
+}
+
+/// Kept entries and dropped duplicate indices.
+type Scan<'a> = (Vec<(u32, &'a str)>, Vec<u32>);
+
+fn msg_entry<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, (u32, &'a str), E> {
+    pair(
+        braced(unsigned_number),
+        preceded(braced(take_till(|ch| ch == '}')), braced(take_till(|ch| ch == '}'))),
+    )(i)
+}
+
+fn braced<'a, O, E: ParseError<&'a str>, F: Fn(&'a str) -> IResult<&'a str, O, E>>(
+    parser: F,
+) -> impl Fn(&'a str) -> IResult<&'a str, O, E> {
+    delimited(char('{'), parser, char('}'))
+}
+
+impl<R: for<'a> TryFrom<AnyRecipe<&'a str>, Error = RecipeError>> RecipeBook<R> {
+    /// Loads FOCRAFT.MSG from disk, e.g. `text/engl/FOCRAFT.MSG`.
+    pub fn from_msg_file(path: impl AsRef<Path>, encoding: MsgEncoding) -> Result<Self, LoadError> {
+        Self::from_msg(&MsgFile::open(path, encoding)?)
+    }
+    pub fn from_msg_reader(reader: impl Read, encoding: MsgEncoding) -> Result<Self, LoadError> {
+        Self::from_msg(&MsgFile::from_reader(reader, encoding)?)
+    }
+    pub fn from_msg(msg: &MsgFile) -> Result<Self, LoadError> {
+        Ok(Self::try_from_iter(msg.entries()?.into_iter())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OwnedRecipeBook, UserFriendlyRecipeBook};
+
+    const MSG: &str = "\
+        # FOCRAFT.MSG\n\
+        {1}{}{PID_MEAT_JERKY@Вяленое мясо.@@SK_OUTDOORSMAN 100@PID_RAD_MEAT 4&PID_SPIRIT 1@PID_FIREPLACE_TOKEN 1@PID_MEAT_JERKY 3&PID_BOTTLE_GLASS 1@script fix_boy@fix_Tribal}\n\
+        {1}{}{duplicate is ignored}\r\n\
+        \n\
+        {2}{}{PID_EMPTY_JET@@@SK_REPAIR 100|SK_SCIENCE 100@PID_BOTTLE_EMPTY 5@@PID_EMPTY_JET 5@exp 10}\n\
+    ";
+
+    #[test]
+    fn load_cp1251_and_utf8() {
+        let cp1251 = encoding_rs::WINDOWS_1251.encode(MSG).0.into_owned();
+        for (bytes, encoding) in [
+            (cp1251.clone(), MsgEncoding::Cp1251),
+            (cp1251, MsgEncoding::Auto),
+            (MSG.as_bytes().to_vec(), MsgEncoding::Utf8),
+            (MSG.as_bytes().to_vec(), MsgEncoding::Auto),
+        ] {
+            let book = UserFriendlyRecipeBook::from_msg_reader(bytes.as_slice(), encoding).unwrap();
+            assert_eq!(vec![&1, &2], book.keys().collect::<Vec<_>>());
+            assert_eq!(Some(&"Вяленое мясо.".to_owned()), book[&1].description());
+        }
+        assert_eq!(vec![1], MsgFile::from_bytes(MSG.as_bytes().to_vec(), MsgEncoding::Utf8).unwrap().duplicates().unwrap());
+    }
+
+    #[test]
+    fn load_errors() {
+        let err = MsgFile::from_bytes(b"{1}{}{ok}\n{2}{}{unterminated".to_vec(), MsgEncoding::Utf8).unwrap().entries().unwrap_err();
+        assert!(matches!(err, LoadError::Msg { line: 2 }), "{err}");
+        let err = OwnedRecipeBook::from_msg_reader(MSG.as_bytes(), MsgEncoding::Utf8).unwrap_err();
+        assert!(matches!(err, LoadError::Recipe(RecipeError::UnexpectedFormat { index: Some(1), .. })), "{err}");
+        assert!(matches!(MsgFile::from_bytes(vec![0xff], MsgEncoding::Utf8), Err(LoadError::InvalidUtf8)));
+    }
+}
@@ -0,0 +1,51 @@
+use nom_prelude::nom::{self, error::{ErrorKind, VerboseError, VerboseErrorKind}};
+
+use crate::{error::{ErrorFrame, ErrorFrameKind, SourcePosition, SyntaxError}, recipe::RecipeField};
+
+pub(crate) fn syntax_error(source: &str, err: nom::Err<VerboseError<&str>>) -> SyntaxError {
+    let errors = match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => err.errors,
+        nom::Err::Incomplete(_) => vec![],
+    };
+    let frames: Vec<_> = errors.into_iter().map(|(remaining, kind)| ErrorFrame {
+        position: SourcePosition::new(source, offset_in(source, remaining)),
+        kind: match kind {
+            VerboseErrorKind::Context(context) => ErrorFrameKind::Context(context),
+            VerboseErrorKind::Char(ch) => ErrorFrameKind::Expected(format!("{ch:?}")),
+            VerboseErrorKind::Nom(kind) => ErrorFrameKind::Expected(describe(kind).to_owned()),
+        },
+    }).collect();
+    let field = frames.iter().find_map(|frame| match frame.kind {
+        ErrorFrameKind::Context(context) => RecipeField::from_name(context),
+        ErrorFrameKind::Expected(_) => None,
+    });
+    let (position, expected) = frames.iter().find_map(|frame| match &frame.kind {
+        ErrorFrameKind::Expected(expected) => Some((frame.position, expected.clone())),
+        ErrorFrameKind::Context(_) => None,
+    }).unwrap_or_else(|| (SourcePosition::new(source, source.len()), describe(ErrorKind::Eof).to_owned()));
+    SyntaxError { index: None, field, position, expected, source: source.to_owned(), frames }
+}
+
+/// Remaining input is always a subslice of the source, even inside `map_parser`.
+fn offset_in(source: &str, remaining: &str) -> usize {
+    (remaining.as_ptr() as usize)
+        .checked_sub(source.as_ptr() as usize)
+        .filter(|offset| *offset + remaining.len() <= source.len())
+        .unwrap_or_else(|| source.len().saturating_sub(remaining.len()))
+}
+
+fn describe(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Digit => "number",
+        ErrorKind::Space => "space",
+        ErrorKind::TakeTill1 => "text",
+        ErrorKind::TakeWhile1 => "key",
+        ErrorKind::Tag => "keyword",
+        ErrorKind::Eof => "end of line",
+        ErrorKind::NonEmpty => "at least one entry",
+        ErrorKind::MapRes => "valid key",
+        ErrorKind::ManyMN => "as many numbers as the count says",
+        ErrorKind::Alt => "one of the alternatives",
+        _ => "valid token",
+    }
+}
@@ -1,41 +1,76 @@
-use crate::{logic::{KeyValue, LogicChain, Logical}, recipe::{SideEffect, AnyRecipe}, key::Key, Recipe};
+use crate::{logic::{KeyValue, LogicChain, LogicNode, Logical}, recipe::{SideEffect, AnyRecipe, RecipeField}, key::Key, Recipe, NodeRecipe};
 use nom_prelude::{complete::*, *};
 
 pub(crate) fn any_recipe<'a, E: ParseError<&'a str>>(ref mut i: &'a str) -> IResult<&'a str, AnyRecipe<&'a str>, E> {
     alt((
         map(preceded(char('!'), cut(numeric_recipe)), AnyRecipe::Numeric),
-        map(recipe, AnyRecipe::Textual),
+        |i| if has_brackets(i) {
+            map(tree_recipe, AnyRecipe::TextualTree)(i)
+        } else {
+            map(recipe, AnyRecipe::Textual)(i)
+        },
     ))(i)
 }
 
+/// Brackets in a requirement field, descriptions may contain them freely.
+///
+/// Relies on the field order of [`recipe`]: name and description, then the five requirement
+/// fields (params to see, params to craft, ingredients, tools, output), then the side effect.
+fn has_brackets(i: &str) -> bool {
+    i.split('@').skip(2).take(5).any(|field| field.contains('('))
+}
+
 fn recipe<'a, E: ParseError<&'a str>>(ref mut i: &'a str) -> IResult<&'a str, Recipe<&'a str, &'a str>, E> {
     let entry = Recipe {
-        name: apply(i, terminated(not_a_dog, a_dog))?,
-        description: apply(i, terminated(opt(not_a_dog), a_dog))?,
-        params_to_see: apply(i, optional_dog_logic_chain)?,
-        params_to_craft: apply(i, optional_dog_logic_chain)?,
-        ingredients: apply(i, dog_logic_chain)?,
-        tools: apply(i, optional_dog_logic_chain)?,
-        output: apply(i, dog_logic_chain)?,
-        side_effect: apply(i, side_effect)?,
+        name: apply(i, field(RecipeField::Name, terminated(not_a_dog, a_dog)))?,
+        description: apply(i, field(RecipeField::Description, terminated(opt(not_a_dog), a_dog)))?,
+        params_to_see: apply(i, field(RecipeField::ParamsToSee, optional_dog_logic_chain))?,
+        params_to_craft: apply(i, field(RecipeField::ParamsToCraft, optional_dog_logic_chain))?,
+        ingredients: apply(i, field(RecipeField::Ingredients, dog_logic_chain))?,
+        tools: apply(i, field(RecipeField::Tools, optional_dog_logic_chain))?,
+        output: apply(i, field(RecipeField::Output, dog_logic_chain))?,
+        side_effect: apply(i, field(RecipeField::SideEffect, side_effect))?,
+    };
+    Ok((i, entry))
+}
+
+/// Textual recipe with bracketed requirements, only tried when a requirement field has brackets.
+fn tree_recipe<'a, E: ParseError<&'a str>>(ref mut i: &'a str) -> IResult<&'a str, NodeRecipe<&'a str, &'a str>, E> {
+    let entry = NodeRecipe {
+        name: apply(i, field(RecipeField::Name, terminated(not_a_dog, a_dog)))?,
+        description: apply(i, field(RecipeField::Description, terminated(opt(not_a_dog), a_dog)))?,
+        params_to_see: apply(i, field(RecipeField::ParamsToSee, optional_dog_logic_node))?,
+        params_to_craft: apply(i, field(RecipeField::ParamsToCraft, optional_dog_logic_node))?,
+        ingredients: apply(i, field(RecipeField::Ingredients, dog_logic_node))?,
+        tools: apply(i, field(RecipeField::Tools, optional_dog_logic_node))?,
+        output: apply(i, field(RecipeField::Output, dog_logic_node))?,
+        side_effect: apply(i, field(RecipeField::SideEffect, side_effect))?,
     };
     Ok((i, entry))
 }
 
 fn numeric_recipe<'a, E: ParseError<&'a str>>(ref mut i: &'a str) -> IResult<&'a str, Recipe<&'a str, u32>, E> {
     let entry = Recipe {
-        name: apply(i, terminated(not_a_dog, a_dog))?,
-        description: apply(i, terminated(opt(not_a_dog), a_dog))?,
-        params_to_see: apply(i, optional_numeric_logic_chain)?,
-        params_to_craft: apply(i, optional_numeric_logic_chain)?,
-        ingredients: apply(i, numeric_logic_chain::<true, _>)?,
-        tools: apply(i, optional_numeric_logic_chain)?,
-        output: apply(i, numeric_logic_chain::<false, _>)?,
-        side_effect: apply(i, side_effect)?,
+        name: apply(i, field(RecipeField::Name, terminated(not_a_dog, a_dog)))?,
+        description: apply(i, field(RecipeField::Description, terminated(opt(not_a_dog), a_dog)))?,
+        params_to_see: apply(i, field(RecipeField::ParamsToSee, optional_numeric_logic_chain))?,
+        params_to_craft: apply(i, field(RecipeField::ParamsToCraft, optional_numeric_logic_chain))?,
+        ingredients: apply(i, field(RecipeField::Ingredients, numeric_logic_chain::<true, _>))?,
+        tools: apply(i, field(RecipeField::Tools, optional_numeric_logic_chain))?,
+        output: apply(i, field(RecipeField::Output, numeric_logic_chain::<false, _>))?,
+        side_effect: apply(i, field(RecipeField::SideEffect, side_effect))?,
     };
     Ok((i, entry))
 }
 
+/// Labels the parser with the recipe field, so errors can tell where they happened.
+fn field<'a, O, E: ParseError<&'a str>, F: Fn(&'a str) -> IResult<&'a str, O, E>>(
+    field: RecipeField,
+    parser: F,
+) -> impl Fn(&'a str) -> IResult<&'a str, O, E> {
+    nom::error::context(field.name(), parser)
+}
+
 fn logic_chain<'a, E: ParseError<&'a str>, K: Key<'a>>(
     ref mut i: &'a str,
 ) -> IResult<&'a str, LogicChain<K>, E> {
@@ -46,6 +81,38 @@ fn logic_chain<'a, E: ParseError<&'a str>, K: Key<'a>>(
     Ok((i, chain))
 }
 
+/// Same precedence as the flat chain: OR binds tighter than AND, brackets override it.
+fn logic_node<'a, E: ParseError<&'a str>, K: Key<'a>>(i: &'a str) -> IResult<&'a str, LogicNode<K>, E> {
+    map(
+        pair(or_node, many0(preceded(space0_delimited(char('&')), or_node))),
+        |(first, rest)| group(first, rest, LogicNode::And),
+    )(i)
+}
+
+fn or_node<'a, E: ParseError<&'a str>, K: Key<'a>>(i: &'a str) -> IResult<&'a str, LogicNode<K>, E> {
+    map(
+        pair(term_node, many0(preceded(space0_delimited(char('|')), term_node))),
+        |(first, rest)| group(first, rest, LogicNode::Or),
+    )(i)
+}
+
+fn term_node<'a, E: ParseError<&'a str>, K: Key<'a>>(i: &'a str) -> IResult<&'a str, LogicNode<K>, E> {
+    alt((
+        delimited(space0_delimited(char('(')), logic_node, space0_delimited(char(')'))),
+        // last, so its error is the one reported for a malformed key-value
+        map(key_value, LogicNode::KeyValue),
+    ))(i)
+}
+
+fn group<K>(first: LogicNode<K>, mut rest: Vec<LogicNode<K>>, make: fn(Vec<LogicNode<K>>) -> LogicNode<K>) -> LogicNode<K> {
+    if rest.is_empty() {
+        first
+    } else {
+        rest.insert(0, first);
+        make(rest)
+    }
+}
+
 fn key_value<'a, 'b, E: ParseError<&'a str>, K: Key<'a>>(i: &'a str) -> IResult<&'a str, KeyValue<K>, E> {
     map_res(
         space0_delimited(separated_pair(word, space1, unsigned_number)),
@@ -88,13 +155,23 @@ fn a_dog<'a, E: ParseError<&'a str>>(i: &'a str) -> IResult<&'a str, char, E> {
 }
 
 fn dog_logic_chain<'a, E: ParseError<&'a str>, K: Key<'a>>(i: &'a str) -> IResult<&'a str, LogicChain<K>, E> {
-    terminated(map_parser(not_a_dog, logic_chain), a_dog)(i)
+    terminated(map_parser(not_a_dog, cut(all_consuming(logic_chain))), a_dog)(i)
 }
 
 fn optional_dog_logic_chain<'a, E: ParseError<&'a str>, K: Key<'a>>(
     i: &'a str,
 ) -> IResult<&'a str, Option<LogicChain<K>>, E> {
-    terminated(opt(map_parser(not_a_dog, logic_chain)), a_dog)(i)
+    terminated(opt(map_parser(not_a_dog, cut(all_consuming(logic_chain)))), a_dog)(i)
+}
+
+fn dog_logic_node<'a, E: ParseError<&'a str>, K: Key<'a>>(i: &'a str) -> IResult<&'a str, LogicNode<K>, E> {
+    terminated(map_parser(not_a_dog, cut(all_consuming(logic_node))), a_dog)(i)
+}
+
+fn optional_dog_logic_node<'a, E: ParseError<&'a str>, K: Key<'a>>(
+    i: &'a str,
+) -> IResult<&'a str, Option<LogicNode<K>>, E> {
+    terminated(opt(map_parser(not_a_dog, cut(all_consuming(logic_node)))), a_dog)(i)
 }
 
 fn numeric_logic_chain<'a, const ORS: bool, E: ParseError<&'a str>>(
@@ -318,6 +395,196 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "serialize")]
+    fn assert_textual_roundtrip(sample: &str) {
+        use crate::NodeRecipe;
+        let original: Recipe<&str, &str> = lex(recipe, sample);
+        let text = original.to_textual().unwrap();
+        assert_eq!(original, lex(recipe, &text));
+
+        let node_recipe: NodeRecipe<&str, &str> = original.into();
+        let node_text = node_recipe.to_textual().unwrap();
+        assert_eq!(text, node_text);
+        let reparsed: NodeRecipe<&str, &str> = lex(recipe, &node_text).into();
+        assert_eq!(node_recipe, reparsed);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn textual_roundtrip() {
+        assert_textual_roundtrip("PID_ZAPLATKA_CRAFT_BASIC@@@@PID_ZAPLATKA_CRAFT_BASIC 1@@PID_ZAPLATKA_CRAFT_BASIC 1@script fix_boy@fix_Tribal");
+        assert_textual_roundtrip("PID_MEAT_JERKY@Meat dried over a fire pit.@@SK_OUTDOORSMAN 100@PID_RAD_MEAT 4&PID_SPIRIT 1@PID_FIREPLACE_TOKEN 1@PID_MEAT_JERKY 3&PID_BOTTLE_GLASS 1@script fix_boy@fix_Tribal");
+        assert_textual_roundtrip("PID_EMPTY_JET@Jet can.@SK_SCIENCE 50@SK_REPAIR 100|SK_SCIENCE 100&SK_DOCTOR 10@PID_BOTTLE_EMPTY 5&PID_CRAFT_L_LINT 5|PID_CRAFT_M_JUNK 1@@PID_EMPTY_JET 5@exp 100");
+        assert_textual_roundtrip("PID_EMPTY_JET@@@@PID_BOTTLE_EMPTY 5@@PID_EMPTY_JET 5@script");
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn textual_exact() {
+        const SAMPLE: &str = "PID_EMPTY_JET@desc@@SK_REPAIR 100 | SK_SCIENCE 100@PID_BOTTLE_EMPTY 5 & PID_CRAFT_L_LINT 5@PID_KNIFE 1@PID_EMPTY_JET 5@script fix_boy@fix_FreeHands";
+        let original: Recipe<&str, &str> = lex(recipe, SAMPLE);
+        assert_eq!(
+            "PID_EMPTY_JET@desc@@SK_REPAIR 100|SK_SCIENCE 100@PID_BOTTLE_EMPTY 5&PID_CRAFT_L_LINT 5@PID_KNIFE 1@PID_EMPTY_JET 5@script fix_boy@fix_FreeHands",
+            original.to_textual().unwrap(),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn textual_nested_node() {
+        use crate::{logic::LogicNode, serialize::{SerializeError, LogicError}, recipe::RecipeField, NodeRecipe};
+        let mut node_recipe: NodeRecipe<&str, &str> = lex(recipe, "PID_A@@@@PID_B 1@@PID_A 1@exp 10").into();
+        let kv = |key| LogicNode::KeyValue(KeyValue { key, value: 1 });
+        node_recipe.ingredients = LogicNode::Or(vec![kv("PID_B"), LogicNode::And(vec![kv("PID_C"), kv("PID_D")])]);
+        assert_eq!(
+            Err(SerializeError::Logic { field: RecipeField::Ingredients, error: LogicError::Nested }),
+            node_recipe.to_textual(),
+        );
+        node_recipe.ingredients = LogicNode::And(vec![LogicNode::And(vec![kv("PID_B")]), LogicNode::Or(vec![kv("PID_C"), kv("PID_D")])]);
+        assert_eq!("PID_A@@@@PID_B 1&PID_C 1|PID_D 1@@PID_A 1@exp 10", node_recipe.to_textual().unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn textual_rejects_line_breaking_text() {
+        use crate::{serialize::SerializeError, recipe::{RecipeBuildError, RecipeField}};
+        let mut recipe = lex(recipe, "PID_A@Knife@@@PID_B 1@@PID_A 1@exp 10");
+        recipe.description = Some("Knife}{2}{");
+        let brace = RecipeBuildError::ForbiddenChar { field: RecipeField::Description, ch: '}' };
+        assert_eq!(Err(SerializeError::Invalid(brace)), recipe.to_textual());
+        recipe.description = None;
+        recipe.name = "PID_A\n";
+        let newline = RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '\n' };
+        assert_eq!(Err(SerializeError::Invalid(newline)), recipe.to_textual());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn textual_bracketed_node() {
+        use crate::{logic::LogicNode, serialize::{SerializeError, LogicError}, recipe::RecipeField, NodeRecipe};
+        let mut node_recipe: NodeRecipe<&str, &str> = lex(recipe, "PID_A@@@@PID_B 1@@PID_A 1@exp 10").into();
+        let kv = |key| LogicNode::KeyValue(KeyValue { key, value: 1 });
+        node_recipe.ingredients = LogicNode::Or(vec![kv("PID_B"), LogicNode::And(vec![kv("PID_C"), kv("PID_D")])]);
+        let text = node_recipe.to_textual_bracketed().unwrap();
+        assert_eq!("PID_A@@@@PID_B 1|(PID_C 1&PID_D 1)@@PID_A 1@exp 10", text);
+        assert_eq!(AnyRecipe::TextualTree(node_recipe.clone()), lex(any_recipe, &text));
+        node_recipe.output = LogicNode::Or(vec![]);
+        assert_eq!(
+            Err(SerializeError::Logic { field: RecipeField::Output, error: LogicError::EmptyGroup }),
+            node_recipe.to_textual_bracketed(),
+        );
+    }
+
+    #[test]
+    fn bracketed_requirements() {
+        use crate::{error::{RecipeError, RecipeFormat}, NodeRecipe};
+        const SAMPLE: &str = "PID_A@@@SK_REPAIR 100 & (PID_KNIFE 1 | PID_AXE 1 & PID_ROPE 1)@PID_B 1@@PID_A 1@exp 10";
+        let kv = |key, value| LogicNode::KeyValue(KeyValue { key, value });
+        let AnyRecipe::TextualTree(tree) = lex(any_recipe, SAMPLE) else {
+            panic!("bracketed recipe must be parsed as a tree");
+        };
+        assert_eq!(
+            Some(LogicNode::And(vec![
+                kv("SK_REPAIR", 100),
+                LogicNode::And(vec![LogicNode::Or(vec![kv("PID_KNIFE", 1), kv("PID_AXE", 1)]), kv("PID_ROPE", 1)]),
+            ])),
+            tree.params_to_craft,
+        );
+        assert_eq!(LogicNode::KeyValue(KeyValue { key: "PID_B", value: 1 }), tree.ingredients);
+        assert_eq!(
+            lex(logic_node::<_, &str>, "A 1|B 1&C 1|D 1"),
+            lex(logic_chain::<_, &str>, "A 1|B 1&C 1|D 1").logic_nodes::<&str>(),
+        );
+        assert!(matches!(lex(any_recipe, "PID_A@@@SK_REPAIR 100|SK_SCIENCE 100@PID_B 1@@PID_A 1@exp 10"), AnyRecipe::Textual(_)));
+        assert!(matches!(lex(any_recipe, "PID_A@Knife (sharp)@@@PID_B 1@@PID_A 1@exp 10"), AnyRecipe::Textual(_)));
+
+        let lines = [(7, SAMPLE)];
+        let err = RecipeBook::<Recipe<&str, &str>>::try_from_iter(lines.into_iter()).unwrap_err();
+        assert_eq!(RecipeError::UnexpectedFormat { index: Some(7), expected: RecipeFormat::Textual, found: RecipeFormat::Bracketed }, err);
+        let book = RecipeBook::<NodeRecipe<String, String>>::try_from_iter(lines.into_iter()).unwrap();
+        assert_eq!(LogicNode::KeyValue(KeyValue { key: "PID_B".to_owned(), value: 1 }), book[&7].ingredients);
+
+        let (_, errors) = RecipeBook::<NodeRecipe<String, String>>::from_iter_recovering([(8, "PID_A@@@(SK_REPAIR 100@PID_B 1@@PID_A 1@exp 10")].into_iter());
+        assert_eq!(Some(crate::recipe::RecipeField::ParamsToCraft), match &errors[0] {
+            RecipeError::Syntax(syntax) => syntax.field,
+            other => panic!("{other:?}"),
+        });
+    }
+
+    #[test]
+    fn lower_node_recipe() {
+        use crate::{logic::LowerError, recipe::{RecipeField, RecipeLowerError}, NodeRecipe};
+        let (_, textual) = meat_jerkies();
+        let node: NodeRecipe<&str, &str> = textual.clone().into();
+        assert_eq!(Ok(textual), Recipe::try_from(node));
+
+        let AnyRecipe::TextualTree(tree) = lex(any_recipe, "PID_A@@@@PID_B 1|(PID_C 1&PID_D 1)@@PID_A 1@exp 10") else {
+            panic!("bracketed recipe must be parsed as a tree");
+        };
+        let and = LogicNode::And(vec![
+            LogicNode::KeyValue(KeyValue { key: "PID_C", value: 1 }),
+            LogicNode::KeyValue(KeyValue { key: "PID_D", value: 1 }),
+        ]);
+        assert_eq!(
+            Err(RecipeLowerError { field: RecipeField::Ingredients, error: LowerError::AndInsideOr(and) }),
+            Recipe::try_from(tree),
+        );
+    }
+
+    #[test]
+    fn brackets_field_layout() {
+        let fields = ["PID_A", "", "", "", "PID_B 1", "", "PID_A 1", "exp 10"];
+        for position in 0..fields.len() {
+            let mut fields = fields.map(String::from);
+            fields[position].push('(');
+            assert_eq!((2..7).contains(&position), has_brackets(&fields.join("@")), "{position}");
+        }
+    }
+
+    #[test]
+    fn flat_chain_tail() {
+        use crate::{error::RecipeError, recipe::RecipeField};
+        let lines = [(1, "PID_A@@@@PID_B 1 PID_C 1@@PID_A 1@exp 10")];
+        let err = RecipeBook::<Recipe<&str, &str>>::try_from_iter(lines.into_iter()).unwrap_err();
+        let RecipeError::Syntax(syntax) = &err else {
+            panic!("{err:?}");
+        };
+        assert_eq!(Some(RecipeField::Ingredients), syntax.field);
+    }
+
+    #[test]
+    fn syntax_error_position() {
+        use crate::{error::{RecipeError, RecipeFormat}, recipe::RecipeField};
+        let lines = [
+            (1, "PID_A@@@@PID_B 1@@PID_A 1@exp 10"),
+            (2, "PID_A@Пустая@@SK_REPAIR x100@PID_B 1@@PID_A 1@exp 10"),
+        ];
+        let err = RecipeBook::<Recipe<&str, &str>>::try_from_iter(lines.into_iter()).unwrap_err();
+        let RecipeError::Syntax(syntax) = &err else {
+            panic!("{err:?}");
+        };
+        assert_eq!(Some(2), err.index());
+        assert_eq!(Some(RecipeField::ParamsToCraft), syntax.field);
+        assert_eq!((30, 1, 25), (syntax.position.offset, syntax.position.line, syntax.position.column));
+        assert!(err.render().contains("\n     |                         ^ expected number"), "{}", err.render());
+
+        let err = RecipeBook::<Recipe<&str, u32>>::try_from_iter(lines[..1].iter().copied()).unwrap_err();
+        assert_eq!(RecipeError::UnexpectedFormat { index: Some(1), expected: RecipeFormat::Numeric, found: RecipeFormat::Textual }, err);
+    }
+
+    #[test]
+    fn recovering_book() {
+        let lines = [
+            (1, "PID_A@@@@PID_B 1@@PID_A 1@exp 10"),
+            (2, "PID_C@@@SK_REPAIR x100@PID_B 1@@PID_C 1@exp 10"),
+            (3, "PID_D@@@@PID_B 1@@PID_D 1@exp 10"),
+            (4, "PID_E@@@@@@PID_E 1@exp 10"),
+        ];
+        let (book, errors) = RecipeBook::<Recipe<&str, &str>>::from_iter_recovering(lines.into_iter());
+        assert_eq!(vec![&1, &3], book.keys().collect::<Vec<_>>());
+        assert_eq!(vec![Some(2), Some(4)], errors.iter().map(|err| err.index()).collect::<Vec<_>>());
+    }
+
     #[test]
     fn lex_forp_crafts() {
         for dir in &["../../../FO4RP/text/engl"] {
@@ -353,6 +620,23 @@ mod tests {
         (numeric, textual)
     }
 
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn numeric_roundtrip() {
+        let (numeric, _) = meat_jerkies();
+        let net = numeric.to_numeric().unwrap();
+        assert_eq!(
+            "!PID_MEAT_JERKY@Meat dried over a fire pit.@0 0 1 0 1 217 1 100 1 0 2 1440 125 2 4 1 2 0 0 1 3979 1 1 1 0 2 284 542 2 3 1 script",
+            net,
+        );
+        let reparsed: Recipe<&str, u32> = lex(any_recipe, &net).try_into().unwrap();
+        assert_eq!(numeric, reparsed);
+
+        let with_ors = "!PID_A@@1 217 1 50 1 0 2 217 218 2 100 100 2 1 0 3 1 2 3 3 4 5 6 3 0 1 0 0 0 1 0 1 9 1 1 exp";
+        let recipe: Recipe<&str, u32> = lex(any_recipe, with_ors).try_into().unwrap();
+        assert_eq!(with_ors, recipe.to_numeric().unwrap());
+    }
+
     #[test]
     fn sameish_meat_jerky() {
         let (numeric, textual) = meat_jerkies();
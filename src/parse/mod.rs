@@ -1,15 +1,55 @@
-use crate::{recipe::AnyRecipe, RecipeError, book::RecipeBook};
+use crate::{recipe::AnyRecipe, RecipeError, book::{RecipeBook, SeparatorConfig}};
 
+mod error;
 mod lexer;
 
 impl<'a, R: TryFrom<AnyRecipe<&'a str>, Error=RecipeError>> RecipeBook<R> {
+    /// Separators are marked by the default [`SeparatorConfig`].
     pub fn try_from_iter(iter: impl Iterator<Item = (u32, &'a str)>) -> Result<Self, RecipeError> {
-        let mut book = Self{recipes: Default::default()};
+        let mut book = Self::default();
+        let config = SeparatorConfig::default();
         for (index, str) in iter {
-            let recipe = lexer::any_recipe::<nom_prelude::nom::error::VerboseError<&'a str>>(str).map_err(|err| format!("Recipe #{} has err: {:?}", index, err))?.1;
-            let recipe = recipe.try_into()?;
-            book.recipes.insert(index, recipe);
+            let recipe = Self::parse_recipe(index, str, &config)?;
+            book.insert_parsed(index, recipe);
         }
         Ok(book)
     }
+    /// Keeps every recipe that parses and collects an error for each one that doesn't.
+    pub fn from_iter_recovering(iter: impl Iterator<Item = (u32, &'a str)>) -> (Self, Vec<RecipeError>) {
+        let mut book = Self::default();
+        let mut errors = Vec::new();
+        let config = SeparatorConfig::default();
+        for (index, str) in iter {
+            match Self::parse_recipe(index, str, &config) {
+                Ok(recipe) => book.insert_parsed(index, recipe),
+                Err(err) => errors.push(err),
+            }
+        }
+        (book, errors)
+    }
+    /// Recipe with whether it is a separator.
+    fn parse_recipe(index: u32, str: &'a str, config: &SeparatorConfig) -> Result<(R, bool), RecipeError> {
+        let recipe = lexer::any_recipe::<nom_prelude::nom::error::VerboseError<&'a str>>(str)
+            .map_err(|err| RecipeError::Syntax(error::syntax_error(str, err)).with_index(index))?.1;
+        let separator = recipe.is_separator(config);
+        Ok((recipe.try_into().map_err(|err: RecipeError| err.with_index(index))?, separator))
+    }
+    fn insert_parsed(&mut self, index: u32, (recipe, separator): (R, bool)) {
+        self.recipes.insert(index, recipe);
+        if separator {
+            self.separators.insert(index);
+        } else {
+            self.separators.remove(&index);
+        }
+    }
+}
+
+impl AnyRecipe<&str> {
+    fn is_separator(&self, config: &SeparatorConfig) -> bool {
+        match self {
+            AnyRecipe::Textual(recipe) => config.is_separator(recipe),
+            AnyRecipe::TextualTree(recipe) => config.is_separator(recipe),
+            AnyRecipe::Numeric(recipe) => config.is_separator(recipe),
+        }
+    }
 }
@@ -1,12 +1,12 @@
-use crate::{Recipe, RecipeError, NodeRecipe, typed::{ParamLogic, ItemLogic}, logic::{LogicType, LogicChain}, key::KeyMeaning};
+use crate::{Recipe, RecipeError, error::RecipeFormat, NodeRecipe, typed::{ParamLogic, ItemLogic}, logic::{LogicType, LogicVisit, LogicChain, LogicNode, LowerError, LogicFolder, KeyValue, Logical}, key::{is_word, is_word_char, KeyMeaning}};
 
 impl<'a> TryFrom<AnyRecipe<&'a str>> for Recipe<&'a str, &'a str> {
     type Error = RecipeError;
 
     fn try_from(recipe: AnyRecipe<&'a str>) -> Result<Self, Self::Error> {
         match recipe {
-            AnyRecipe::Numeric(recipe) => Err(format!("Unexpected numeric recipe: {:?}", recipe)),
             AnyRecipe::Textual(recipe) => Ok(recipe),
+            other => Err(other.unexpected(RecipeFormat::Textual)),
         }
     }
 }
@@ -17,38 +17,64 @@ impl<'a> TryFrom<AnyRecipe<&'a str>> for Recipe<&'a str, u32> {
     fn try_from(recipe: AnyRecipe<&'a str>) -> Result<Self, Self::Error> {
         match recipe {
             AnyRecipe::Numeric(recipe) => Ok(recipe),
-            AnyRecipe::Textual(recipe) => Err(format!("Unexpected textual recipe: {:?}", recipe)),
+            other => Err(other.unexpected(RecipeFormat::Numeric)),
         }    
     }
 }
 
-impl<'a, K: Clone> TryFrom<AnyRecipe<&'a str>> for NodeRecipe<String, K>
-    where Recipe<&'a str, K>: TryFrom<AnyRecipe<&'a str>, Error = RecipeError>
-{
+impl<'a> TryFrom<AnyRecipe<&'a str>> for NodeRecipe<String, u32> {
     type Error = RecipeError;
 
     fn try_from(recipe: AnyRecipe<&'a str>) -> Result<Self, Self::Error> {
-        Ok((Recipe::try_from(recipe)?).into())
+        Ok((Recipe::<&str, u32>::try_from(recipe)?).into())
     }
 }
 
-impl<'a> TryFrom<AnyRecipe<&'a str>> for NodeRecipe<String, String>
-    where Recipe<&'a str, &'a str>: TryFrom<AnyRecipe<&'a str>, Error = RecipeError>
-{
+impl<'a> TryFrom<AnyRecipe<&'a str>> for NodeRecipe<String, &'a str> {
+    type Error = RecipeError;
+
+    fn try_from(recipe: AnyRecipe<&'a str>) -> Result<Self, Self::Error> {
+        textual_node_recipe(recipe)
+    }
+}
+
+impl<'a> TryFrom<AnyRecipe<&'a str>> for NodeRecipe<String, String> {
     type Error = RecipeError;
 
     fn try_from(recipe: AnyRecipe<&'a str>) -> Result<Self, Self::Error> {
-        Ok((Recipe::try_from(recipe)?).into())
+        textual_node_recipe(recipe)
     }
 }
 
-#[derive(Debug)]
+fn textual_node_recipe<'a, K: From<&'a str>>(recipe: AnyRecipe<&'a str>) -> Result<NodeRecipe<String, K>, RecipeError> {
+    match recipe {
+        AnyRecipe::TextualTree(recipe) => Ok(recipe.into()),
+        other => Ok((Recipe::<&str, &str>::try_from(other)?).into()),
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub enum AnyRecipe<S> {
     Textual(Recipe<S, S>),
+    /// Textual recipe with bracketed requirements, which only `LogicNode` can hold.
+    TextualTree(NodeRecipe<S, S>),
     Numeric(Recipe<S, u32>),
 }
 
-#[derive(PartialEq, Debug)]
+impl<S> AnyRecipe<S> {
+    pub fn format(&self) -> RecipeFormat {
+        match self {
+            AnyRecipe::Textual(_) => RecipeFormat::Textual,
+            AnyRecipe::TextualTree(_) => RecipeFormat::Bracketed,
+            AnyRecipe::Numeric(_) => RecipeFormat::Numeric,
+        }
+    }
+    fn unexpected(&self, expected: RecipeFormat) -> RecipeError {
+        RecipeError::UnexpectedFormat { index: None, expected, found: self.format() }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone)]
 pub struct GenericRecipe<S, L> {
     pub(crate) name: S,
     pub(crate) description: Option<S>,
@@ -82,6 +108,226 @@ impl<S, L> GenericRecipe<S, L> {
     pub fn output(&self) -> ItemLogic<L> {
         ItemLogic::new(&self.output)
     }
+    pub fn side_effect(&self) -> &SideEffect<S> {
+        &self.side_effect
+    }
+    pub fn builder(name: S) -> RecipeBuilder<S, L> {
+        RecipeBuilder {
+            name,
+            description: None,
+            params_to_see: None,
+            params_to_craft: None,
+            ingredients: None,
+            tools: None,
+            output: None,
+            side_effect: None,
+        }
+    }
+}
+
+/// Setters check the same invariants as [`RecipeBuilder::build`].
+impl<S: AsRef<str>, L: LogicVisit> GenericRecipe<S, L> where L::Key: std::fmt::Display {
+    pub fn set_name(&mut self, name: S) -> Result<(), RecipeBuildError> {
+        self.name = checked(name, check_name)?;
+        Ok(())
+    }
+    pub fn set_description(&mut self, description: Option<S>) -> Result<(), RecipeBuildError> {
+        self.description = check_description(description)?;
+        Ok(())
+    }
+    pub fn set_side_effect(&mut self, side_effect: SideEffect<S>) -> Result<(), RecipeBuildError> {
+        self.side_effect = check_side_effect(side_effect)?;
+        Ok(())
+    }
+    pub fn set_params_to_see(&mut self, params: Option<L>) -> Result<(), RecipeBuildError> {
+        self.params_to_see = check_optional(params, RecipeField::ParamsToSee)?;
+        Ok(())
+    }
+    pub fn set_params_to_craft(&mut self, params: Option<L>) -> Result<(), RecipeBuildError> {
+        self.params_to_craft = check_optional(params, RecipeField::ParamsToCraft)?;
+        Ok(())
+    }
+    pub fn set_ingredients(&mut self, ingredients: L) -> Result<(), RecipeBuildError> {
+        self.ingredients = check_logic(ingredients, RecipeField::Ingredients)?;
+        Ok(())
+    }
+    pub fn set_tools(&mut self, tools: Option<L>) -> Result<(), RecipeBuildError> {
+        self.tools = check_optional(tools, RecipeField::Tools)?;
+        Ok(())
+    }
+    pub fn set_output(&mut self, output: L) -> Result<(), RecipeBuildError> {
+        self.output = check_logic(output, RecipeField::Output)?;
+        Ok(())
+    }
+}
+
+/// Assembles a recipe without going through MSG text, see [`GenericRecipe::builder`].
+#[derive(Debug, Clone)]
+pub struct RecipeBuilder<S, L> {
+    name: S,
+    description: Option<S>,
+    params_to_see: Option<L>,
+    params_to_craft: Option<L>,
+    ingredients: Option<L>,
+    tools: Option<L>,
+    output: Option<L>,
+    side_effect: Option<SideEffect<S>>,
+}
+
+impl<S, L> RecipeBuilder<S, L> {
+    pub fn description(mut self, description: S) -> Self {
+        self.description = Some(description);
+        self
+    }
+    pub fn params_to_see(mut self, params: L) -> Self {
+        self.params_to_see = Some(params);
+        self
+    }
+    pub fn params_to_craft(mut self, params: L) -> Self {
+        self.params_to_craft = Some(params);
+        self
+    }
+    pub fn ingredients(mut self, ingredients: L) -> Self {
+        self.ingredients = Some(ingredients);
+        self
+    }
+    pub fn tools(mut self, tools: L) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+    pub fn output(mut self, output: L) -> Self {
+        self.output = Some(output);
+        self
+    }
+    pub fn side_effect(mut self, side_effect: SideEffect<S>) -> Self {
+        self.side_effect = Some(side_effect);
+        self
+    }
+}
+
+impl<S: AsRef<str>, L: LogicVisit> RecipeBuilder<S, L> where L::Key: std::fmt::Display {
+    /// Ingredients, output and side effect are required. Every field must be something
+    /// `to_textual` can write: non-empty text without `@`, `}` or line breaks, a name not starting with `!`,
+    /// single-word keys and script functions, requirement blocks without empty groups.
+    pub fn build(self) -> Result<GenericRecipe<S, L>, RecipeBuildError> {
+        let required = |logic: Option<L>, field| check_logic(logic.ok_or(RecipeBuildError::Missing(field))?, field);
+        Ok(GenericRecipe {
+            name: checked(self.name, check_name)?,
+            description: check_description(self.description)?,
+            params_to_see: check_optional(self.params_to_see, RecipeField::ParamsToSee)?,
+            params_to_craft: check_optional(self.params_to_craft, RecipeField::ParamsToCraft)?,
+            ingredients: required(self.ingredients, RecipeField::Ingredients)?,
+            tools: check_optional(self.tools, RecipeField::Tools)?,
+            output: required(self.output, RecipeField::Output)?,
+            side_effect: check_side_effect(self.side_effect.ok_or(RecipeBuildError::Missing(RecipeField::SideEffect))?)?,
+        })
+    }
+}
+
+/// Recipe invariant broken by a builder, setter or serializer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipeBuildError {
+    /// Required field wasn't set.
+    Missing(RecipeField),
+    /// Empty text, or a requirement block without key-values or with an empty `And`/`Or`.
+    Empty(RecipeField),
+    /// `@`, `}` or a line break in text, `!` leading the name or a non-word char in a script
+    /// function.
+    ForbiddenChar { field: RecipeField, ch: char },
+    /// Requirement key which isn't a single word.
+    Key(RecipeField),
+}
+
+impl std::fmt::Display for RecipeBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecipeBuildError::Missing(field) => write!(f, "{field} is required"),
+            RecipeBuildError::Empty(field) => write!(f, "{field} is empty"),
+            RecipeBuildError::ForbiddenChar { field, ch } => write!(f, "{field} contains forbidden char {ch:?}"),
+            RecipeBuildError::Key(field) => write!(f, "{field} has a key which is not a single word"),
+        }
+    }
+}
+
+impl std::error::Error for RecipeBuildError {}
+
+/// Chars which would end a text field, or the `{index}{}{text}` MSG line, early.
+const FORBIDDEN_CHARS: [char; 4] = ['@', '}', '\n', '\r'];
+
+/// Non-empty text which stays inside its field. Shared with the serializers.
+pub(crate) fn check_text(text: &str, field: RecipeField) -> Result<(), RecipeBuildError> {
+    if text.is_empty() {
+        return Err(RecipeBuildError::Empty(field));
+    }
+    match text.chars().find(|ch| FORBIDDEN_CHARS.contains(ch)) {
+        Some(ch) => Err(RecipeBuildError::ForbiddenChar { field, ch }),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn check_name(name: &str) -> Result<(), RecipeBuildError> {
+    // `!` marks the numeric format
+    if name.starts_with('!') {
+        return Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '!' });
+    }
+    check_text(name, RecipeField::Name)
+}
+
+pub(crate) fn check_script(module: &str, function: &str) -> Result<(), RecipeBuildError> {
+    const FIELD: RecipeField = RecipeField::SideEffect;
+    // truncated script, as sent by the server
+    if module.is_empty() && function.is_empty() {
+        return Ok(());
+    }
+    check_text(module, FIELD)?;
+    if let Some(ch) = function.chars().find(|ch| !is_word_char(*ch)) {
+        return Err(RecipeBuildError::ForbiddenChar { field: FIELD, ch });
+    }
+    check_text(function, FIELD)
+}
+
+fn checked<S: AsRef<str>>(text: S, check: impl FnOnce(&str) -> Result<(), RecipeBuildError>) -> Result<S, RecipeBuildError> {
+    check(text.as_ref())?;
+    Ok(text)
+}
+
+fn check_description<S: AsRef<str>>(description: Option<S>) -> Result<Option<S>, RecipeBuildError> {
+    description.map(|description| checked(description, |text| check_text(text, RecipeField::Description))).transpose()
+}
+
+fn check_side_effect<S: AsRef<str>>(side_effect: SideEffect<S>) -> Result<SideEffect<S>, RecipeBuildError> {
+    if let SideEffect::Script { module, function } = &side_effect {
+        check_script(module.as_ref(), function.as_ref())?;
+    }
+    Ok(side_effect)
+}
+
+fn check_logic<L: LogicVisit>(logic: L, field: RecipeField) -> Result<L, RecipeBuildError>
+where
+    L::Key: std::fmt::Display,
+{
+    struct Invalid(RecipeField);
+    impl<K: std::fmt::Display> LogicFolder<K> for Invalid {
+        type Output = Result<(), RecipeBuildError>;
+        fn key_value(&mut self, kv: &KeyValue<K>) -> Self::Output {
+            if is_word(&kv.key.to_string()) { Ok(()) } else { Err(RecipeBuildError::Key(self.0)) }
+        }
+        fn group(&mut self, _logical: Logical, children: Vec<Self::Output>) -> Self::Output {
+            if children.is_empty() {
+                return Err(RecipeBuildError::Empty(self.0));
+            }
+            children.into_iter().collect()
+        }
+    }
+    logic.fold(&mut Invalid(field))?;
+    Ok(logic)
+}
+
+fn check_optional<L: LogicVisit>(logic: Option<L>, field: RecipeField) -> Result<Option<L>, RecipeBuildError>
+where
+    L::Key: std::fmt::Display,
+{
+    logic.map(|logic| check_logic(logic, field)).transpose()
 }
 
 impl<K, S: Clone, L: LogicType<Key = K>> GenericRecipe<S, L> {
@@ -101,9 +347,52 @@ impl<K, S: Clone, L: LogicType<Key = K>> GenericRecipe<S, L> {
     }
 }
 
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub enum RecipeField {
+    Name,
+    Description,
+    ParamsToSee,
+    ParamsToCraft,
+    Ingredients,
+    Tools,
+    Output,
+    SideEffect,
+}
+impl RecipeField {
+    pub const ALL: [RecipeField; 8] = [
+        RecipeField::Name,
+        RecipeField::Description,
+        RecipeField::ParamsToSee,
+        RecipeField::ParamsToCraft,
+        RecipeField::Ingredients,
+        RecipeField::Tools,
+        RecipeField::Output,
+        RecipeField::SideEffect,
+    ];
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|field| field.name() == name)
+    }
+    pub fn name(self) -> &'static str {
+        match self {
+            RecipeField::Name => "name",
+            RecipeField::Description => "description",
+            RecipeField::ParamsToSee => "params_to_see",
+            RecipeField::ParamsToCraft => "params_to_craft",
+            RecipeField::Ingredients => "ingredients",
+            RecipeField::Tools => "tools",
+            RecipeField::Output => "output",
+            RecipeField::SideEffect => "side_effect",
+        }
+    }
+}
+impl std::fmt::Display for RecipeField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
 #[derive(PartialEq, Debug, Clone)]
-#[allow(dead_code)]
-pub(crate) enum SideEffect<S> {
+pub enum SideEffect<S> {
     Script { module: S, function: S },
     Experience(u32),
 }
@@ -140,6 +429,21 @@ impl<'a, K: Clone, K2: From<K>> From<Recipe<&'a str, K>> for NodeRecipe<String,
     }
 }
 
+impl<'a, K: Clone, K2: From<K>> From<NodeRecipe<&'a str, K>> for NodeRecipe<String, K2> {
+    fn from(value: NodeRecipe<&'a str, K>) -> Self {
+        NodeRecipe {
+            name: value.name.into(),
+            description: value.description.map(Into::into),
+            params_to_see: value.params_to_see.map(LogicNode::convert),
+            params_to_craft: value.params_to_craft.map(LogicNode::convert),
+            ingredients: value.ingredients.convert(),
+            tools: value.tools.map(LogicNode::convert),
+            output: value.output.convert(),
+            side_effect: value.side_effect.convert(),
+        }
+    }
+}
+
 impl<'a, K: Clone, K2: From<K>> From<Recipe<&'a str, K>> for NodeRecipe<&'a str, K2> {
     fn from(value: Recipe<&'a str, K>) -> Self {
         NodeRecipe {
@@ -153,4 +457,118 @@ impl<'a, K: Clone, K2: From<K>> From<Recipe<&'a str, K>> for NodeRecipe<&'a str,
             side_effect: value.side_effect.convert(),
         }
     }
-}
\ No newline at end of file
+}
+/// Requirement block of a `NodeRecipe` which doesn't fit the flat chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecipeLowerError<K> {
+    pub field: RecipeField,
+    pub error: LowerError<K>,
+}
+
+impl<K: std::fmt::Display> std::fmt::Display for RecipeLowerError<K> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.error)
+    }
+}
+
+impl<K: std::fmt::Display + std::fmt::Debug> std::error::Error for RecipeLowerError<K> {}
+
+impl<S, K> TryFrom<NodeRecipe<S, K>> for Recipe<S, K> {
+    type Error = RecipeLowerError<K>;
+
+    fn try_from(value: NodeRecipe<S, K>) -> Result<Self, Self::Error> {
+        let lower = |node: LogicNode<K>, field| LogicChain::try_from(node).map_err(|error| RecipeLowerError { field, error });
+        let lower_opt = |node: Option<LogicNode<K>>, field| node.map(|node| lower(node, field)).transpose();
+        Ok(Recipe {
+            name: value.name,
+            description: value.description,
+            params_to_see: lower_opt(value.params_to_see, RecipeField::ParamsToSee)?,
+            params_to_craft: lower_opt(value.params_to_craft, RecipeField::ParamsToCraft)?,
+            ingredients: lower(value.ingredients, RecipeField::Ingredients)?,
+            tools: lower_opt(value.tools, RecipeField::Tools)?,
+            output: lower(value.output, RecipeField::Output)?,
+            side_effect: value.side_effect,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kv(key: &'static str, value: u32) -> LogicNode<&'static str> {
+        LogicNode::KeyValue(KeyValue::new(key, value))
+    }
+
+    #[test]
+    fn builder() {
+        let recipe = NodeRecipe::builder("PID_MEAT_JERKY")
+            .description("Jerky")
+            .ingredients(LogicNode::Or(vec![kv("PID_MEAT", 4), kv("PID_RAD_MEAT", 4)]))
+            .tools(kv("PID_FIREPLACE_TOKEN", 1))
+            .output(kv("PID_MEAT_JERKY", 3))
+            .side_effect(SideEffect::Experience(10))
+            .build()
+            .unwrap();
+        assert_eq!(Some(&"Jerky"), recipe.description());
+        assert_eq!(&SideEffect::Experience(10), recipe.side_effect());
+        assert_eq!(None, recipe.params_to_see().map(|params| params.logic().clone()));
+
+        let mut edited = recipe.clone();
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::Output)), edited.set_output(LogicNode::And(vec![])));
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::Name)), edited.set_name(""));
+        assert_eq!(recipe, edited);
+    }
+
+    #[test]
+    fn builder_invariants() {
+        let base = || NodeRecipe::builder("PID_A").output(kv("PID_A", 1)).side_effect(SideEffect::Experience(0));
+        assert_eq!(Err(RecipeBuildError::Missing(RecipeField::Ingredients)), base().build());
+        let empty = base().ingredients(LogicNode::And(vec![kv("PID_B", 1), LogicNode::Or(vec![])])).build();
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::Ingredients)), empty);
+        let chain = Recipe::builder("PID_A")
+            .ingredients(LogicChain::new(KeyValue::new("PID_B", 1), vec![(Logical::Or, KeyValue::new("PID_C", 1))]))
+            .output(LogicChain::new(KeyValue::new("PID_A", 1), vec![]))
+            .build();
+        assert_eq!(Err(RecipeBuildError::Missing(RecipeField::SideEffect)), chain);
+    }
+
+    #[test]
+    fn builder_rejects_unwritable_text() {
+        let base = |name| NodeRecipe::builder(name).ingredients(kv("PID_B", 1)).output(kv("PID_A", 1)).side_effect(SideEffect::Experience(0));
+        assert!(base("PID_A").build().is_ok());
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '!' }), base("!PID_A").build());
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '@' }), base("PID@A").build());
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::Description)), base("PID_A").description("").build());
+        let at = RecipeBuildError::ForbiddenChar { field: RecipeField::Description, ch: '@' };
+        assert_eq!(Err(at), base("PID_A").description("mail@home").build());
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '}' }), base("PID_A}").build());
+        let newline = RecipeBuildError::ForbiddenChar { field: RecipeField::Description, ch: '\n' };
+        assert_eq!(Err(newline), base("PID_A").description("two\nlines").build());
+        assert_eq!(Err(RecipeBuildError::Key(RecipeField::Output)), base("PID_A").output(kv("PID A", 1)).build());
+
+        let script = |module, function| base("PID_A").side_effect(SideEffect::Script { module, function }).build();
+        assert!(script("fix_boy", "fix_Tribal").is_ok());
+        assert!(script("", "").is_ok());
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::SideEffect)), script("fix_boy", ""));
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::SideEffect)), script("", "fix_Tribal"));
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::SideEffect, ch: '@' }), script("fix@boy", "fix"));
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::SideEffect, ch: '-' }), script("fix_boy", "fix-it"));
+    }
+
+    #[test]
+    fn setters_reject_unwritable_text() {
+        let mut recipe = NodeRecipe::builder("PID_A").ingredients(kv("PID_B", 1)).output(kv("PID_A", 1)).side_effect(SideEffect::Experience(0)).build().unwrap();
+        let original = recipe.clone();
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '!' }), recipe.set_name("!PID_A"));
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Name, ch: '@' }), recipe.set_name("PID@A"));
+        assert_eq!(Err(RecipeBuildError::Empty(RecipeField::Description)), recipe.set_description(Some("")));
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::Description, ch: '@' }), recipe.set_description(Some("a@b")));
+        let script = SideEffect::Script { module: "fix_boy", function: "fix it" };
+        assert_eq!(Err(RecipeBuildError::ForbiddenChar { field: RecipeField::SideEffect, ch: ' ' }), recipe.set_side_effect(script));
+        assert_eq!(Err(RecipeBuildError::Key(RecipeField::Tools)), recipe.set_tools(Some(kv("PID@KNIFE", 1))));
+        assert_eq!(original, recipe);
+        assert_eq!(Ok(()), recipe.set_description(None));
+        assert_eq!(Ok(()), recipe.set_description(Some("Jerky")));
+    }
+}
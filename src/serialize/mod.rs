@@ -0,0 +1,75 @@
+mod textual;
+mod numeric;
+
+use std::fmt::Display;
+
+use crate::{key::is_word, recipe::{check_name, check_text, RecipeBuildError, RecipeField}};
+
+pub use self::textual::TextualLogic;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogicError {
+    /// Key doesn't render as a single word, so the lexer wouldn't read it back.
+    Key(String),
+    /// `And`/`Or` group without any children.
+    EmptyGroup,
+    /// `And` nested inside `Or`, which the flat `KEY N|KEY N&KEY N` grammar can't express.
+    Nested,
+    /// `Or` in a block which has no OR flags in the numeric format.
+    Or,
+}
+
+impl Display for LogicError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogicError::Key(key) => write!(f, "key {key:?} is not a single word"),
+            LogicError::EmptyGroup => write!(f, "empty logic group"),
+            LogicError::Nested => write!(f, "AND nested inside OR can't be written as a flat chain"),
+            LogicError::Or => write!(f, "OR can't be written in this block"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerializeError {
+    /// Text the builder would reject as well.
+    Invalid(RecipeBuildError),
+    Logic { field: RecipeField, error: LogicError },
+}
+
+impl Display for SerializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SerializeError::Invalid(error) => write!(f, "{error}"),
+            SerializeError::Logic { field, error } => write!(f, "{field}: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for SerializeError {}
+
+impl From<RecipeBuildError> for SerializeError {
+    fn from(error: RecipeBuildError) -> Self {
+        SerializeError::Invalid(error)
+    }
+}
+
+
+fn write_text(out: &mut String, text: &str, field: RecipeField) -> Result<(), SerializeError> {
+    check_text(text, field)?;
+    out.push_str(text);
+    Ok(())
+}
+
+/// Writes `name@description@`, shared by both formats.
+fn write_header<S: Display>(out: &mut String, name: &S, description: Option<&S>) -> Result<(), SerializeError> {
+    let name = name.to_string();
+    check_name(&name)?;
+    out.push_str(&name);
+    out.push('@');
+    if let Some(description) = description {
+        write_text(out, &description.to_string(), RecipeField::Description)?;
+    }
+    out.push('@');
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::fmt::Display;
+
+use crate::{logic::{LogicChain, Logical}, recipe::{RecipeField, SideEffect}, Recipe};
+
+use super::{write_header, LogicError, SerializeError};
+
+impl<S: Display> Recipe<S, u32> {
+    /// Renders the recipe the way the server sends it to the client: `!name@description@` followed by
+    /// count-prefixed key, value and OR flag arrays, and the side effect with its arguments truncated.
+    pub fn to_numeric(&self) -> Result<String, SerializeError> {
+        let mut out = String::from("!");
+        write_header(&mut out, &self.name, self.description.as_ref())?;
+        write_optional_chain(&mut out, self.params_to_see.as_ref(), RecipeField::ParamsToSee)?;
+        write_optional_chain(&mut out, self.params_to_craft.as_ref(), RecipeField::ParamsToCraft)?;
+        write_chain::<true>(&mut out, &self.ingredients, RecipeField::Ingredients)?;
+        write_optional_chain(&mut out, self.tools.as_ref(), RecipeField::Tools)?;
+        write_chain::<false>(&mut out, &self.output, RecipeField::Output)?;
+        out.push_str(match self.side_effect {
+            SideEffect::Script { .. } => "script",
+            SideEffect::Experience(_) => "exp",
+        });
+        Ok(out)
+    }
+}
+
+fn write_chain<const ORS: bool>(out: &mut String, chain: &LogicChain<u32>, field: RecipeField) -> Result<(), SerializeError> {
+    let kvs: Vec<_> = std::iter::once(&chain.first).chain(chain.rest.iter().map(|(_, kv)| kv)).collect();
+    // flag of the n-th entry tells whether it is OR-ed with the next one, the last flag is always zero
+    let mut ors: Vec<u32> = chain.rest.iter().map(|(logical, _)| match logical {
+        Logical::And => 0,
+        Logical::Or => 1,
+    }).collect();
+    ors.push(0);
+    if !ORS && ors.contains(&1) {
+        return Err(SerializeError::Logic { field, error: LogicError::Or });
+    }
+    write_spacenums(out, &kvs.iter().map(|kv| kv.key).collect::<Vec<_>>());
+    write_spacenums(out, &kvs.iter().map(|kv| kv.value).collect::<Vec<_>>());
+    if ORS {
+        write_spacenums(out, &ors);
+    }
+    Ok(())
+}
+
+fn write_optional_chain(out: &mut String, chain: Option<&LogicChain<u32>>, field: RecipeField) -> Result<(), SerializeError> {
+    match chain {
+        Some(chain) => write_chain::<true>(out, chain, field),
+        None => {
+            // the server keeps a single zero OR flag even for an empty block
+            write_spacenums(out, &[]);
+            write_spacenums(out, &[]);
+            write_spacenums(out, &[0]);
+            Ok(())
+        }
+    }
+}
+
+fn write_spacenums(out: &mut String, nums: &[u32]) {
+    out.push_str(&nums.len().to_string());
+    out.push(' ');
+    for num in nums {
+        out.push_str(&num.to_string());
+        out.push(' ');
+    }
+}
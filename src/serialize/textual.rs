@@ -0,0 +1,149 @@
+use std::fmt::Display;
+
+use crate::{logic::{KeyValue, LogicChain, LogicNode, Logical}, recipe::{check_script, GenericRecipe, RecipeField, SideEffect}};
+
+use super::{is_word, write_header, LogicError, SerializeError};
+
+/// Logic that can be written in the `KEY N|KEY N&KEY N` dialect of FOCRAFT.MSG.
+pub trait TextualLogic {
+    fn write_textual(&self, out: &mut String) -> Result<(), LogicError>;
+    /// Brackets ANDs nested inside ORs instead of failing with [`LogicError::Nested`]. The game
+    /// can't read such output, only this crate's bracketed grammar can.
+    fn write_textual_bracketed(&self, out: &mut String) -> Result<(), LogicError> {
+        self.write_textual(out)
+    }
+}
+
+impl<K: Display> TextualLogic for LogicChain<K> {
+    fn write_textual(&self, out: &mut String) -> Result<(), LogicError> {
+        write_key_value(out, &self.first)?;
+        for (logical, kv) in &self.rest {
+            out.push(logical_char(*logical));
+            write_key_value(out, kv)?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Display> TextualLogic for LogicNode<K> {
+    fn write_textual(&self, out: &mut String) -> Result<(), LogicError> {
+        write_node(out, self, false)
+    }
+    fn write_textual_bracketed(&self, out: &mut String) -> Result<(), LogicError> {
+        write_node(out, self, true)
+    }
+}
+
+fn write_node<K: Display>(out: &mut String, node: &LogicNode<K>, bracketed: bool) -> Result<(), LogicError> {
+    match node {
+        LogicNode::And(nodes) => write_group(out, nodes, Logical::And, bracketed),
+        LogicNode::Or(nodes) => write_group(out, nodes, Logical::Or, bracketed),
+        LogicNode::KeyValue(kv) => write_key_value(out, kv),
+    }
+}
+
+fn write_group<K: Display>(out: &mut String, nodes: &[LogicNode<K>], logical: Logical, bracketed: bool) -> Result<(), LogicError> {
+    if nodes.is_empty() {
+        return Err(LogicError::EmptyGroup);
+    }
+    for (i, node) in nodes.iter().enumerate() {
+        if i != 0 {
+            out.push(logical_char(logical));
+        }
+        match (node, logical) {
+            (LogicNode::KeyValue(kv), _) => write_key_value(out, kv)?,
+            (LogicNode::And(nodes), Logical::And) | (LogicNode::Or(nodes), Logical::Or) => write_group(out, nodes, logical, bracketed)?,
+            (LogicNode::And(nodes) | LogicNode::Or(nodes), _) if nodes.len() == 1 => write_group(out, nodes, logical, bracketed)?,
+            // OR binds tighter than AND, so only this direction needs brackets
+            (LogicNode::Or(nodes), Logical::And) => write_group(out, nodes, Logical::Or, bracketed)?,
+            (LogicNode::And(nodes), Logical::Or) if bracketed => {
+                out.push('(');
+                write_group(out, nodes, Logical::And, bracketed)?;
+                out.push(')');
+            }
+            (LogicNode::And(_), Logical::Or) => return Err(LogicError::Nested),
+        }
+    }
+    Ok(())
+}
+
+fn write_key_value<K: Display>(out: &mut String, kv: &KeyValue<K>) -> Result<(), LogicError> {
+    let key = kv.key.to_string();
+    if !is_word(&key) {
+        return Err(LogicError::Key(key));
+    }
+    out.push_str(&key);
+    out.push(' ');
+    out.push_str(&kv.value.to_string());
+    Ok(())
+}
+
+fn logical_char(logical: Logical) -> char {
+    match logical {
+        Logical::And => '&',
+        Logical::Or => '|',
+    }
+}
+
+impl<S: Display, L: TextualLogic> GenericRecipe<S, L> {
+    /// Renders the recipe as a FOCRAFT.MSG line, the same dialect `RecipeBook::try_from_iter` reads.
+    pub fn to_textual(&self) -> Result<String, SerializeError> {
+        self.textual(false)
+    }
+    /// Like [`GenericRecipe::to_textual`], but writes ANDs nested inside ORs in brackets. Only
+    /// the bracketed grammar reads the result back, the game doesn't.
+    pub fn to_textual_bracketed(&self) -> Result<String, SerializeError> {
+        self.textual(true)
+    }
+    fn textual(&self, bracketed: bool) -> Result<String, SerializeError> {
+        let mut out = String::new();
+        write_header(&mut out, &self.name, self.description.as_ref())?;
+        write_optional_logic(&mut out, &self.params_to_see, RecipeField::ParamsToSee, bracketed)?;
+        write_optional_logic(&mut out, &self.params_to_craft, RecipeField::ParamsToCraft, bracketed)?;
+        write_logic(&mut out, &self.ingredients, RecipeField::Ingredients, bracketed)?;
+        write_optional_logic(&mut out, &self.tools, RecipeField::Tools, bracketed)?;
+        write_logic(&mut out, &self.output, RecipeField::Output, bracketed)?;
+        write_side_effect(&mut out, &self.side_effect)?;
+        Ok(out)
+    }
+}
+
+fn write_logic<L: TextualLogic>(out: &mut String, logic: &L, field: RecipeField, bracketed: bool) -> Result<(), SerializeError> {
+    let written = if bracketed { logic.write_textual_bracketed(out) } else { logic.write_textual(out) };
+    written.map_err(|error| SerializeError::Logic { field, error })?;
+    out.push('@');
+    Ok(())
+}
+
+fn write_optional_logic<L: TextualLogic>(out: &mut String, logic: &Option<L>, field: RecipeField, bracketed: bool) -> Result<(), SerializeError> {
+    match logic {
+        Some(logic) => write_logic(out, logic, field, bracketed),
+        None => {
+            out.push('@');
+            Ok(())
+        }
+    }
+}
+
+fn write_side_effect<S: Display>(out: &mut String, side_effect: &SideEffect<S>) -> Result<(), SerializeError> {
+    match side_effect {
+        SideEffect::Script { module, function } => {
+            let (module, function) = (module.to_string(), function.to_string());
+            out.push_str("script");
+            check_script(&module, &function)?;
+            // truncated script, as sent by the server
+            if module.is_empty() && function.is_empty() {
+                return Ok(());
+            }
+            out.push(' ');
+            out.push_str(&module);
+            out.push('@');
+            out.push_str(&function);
+        }
+        SideEffect::Experience(exp) => {
+            out.push_str("exp ");
+            out.push_str(&exp.to_string());
+        }
+    }
+    Ok(())
+}